@@ -0,0 +1,76 @@
+use macroquad::prelude::*;
+
+use crate::{Bullet, GameConfig};
+
+// A stationary obstacle that stops player bullets and movement until it is shot down or decays.
+pub struct Barrier {
+    pub rect: Rect,
+    health: u32,
+    max_health: u32,
+    // Seconds left before the barrier crumbles on its own.
+    life: f32,
+}
+
+impl Barrier {
+    // Creates a barrier centered on the given point.
+    pub fn new(center: Vec2, config: &GameConfig) -> Barrier {
+        let size = config.barrier_size;
+        Barrier {
+            rect: Rect::new(center.x - size.x / 2.0, center.y - size.y / 2.0, size.x, size.y),
+            health: config.barrier_health,
+            max_health: config.barrier_health,
+            life: config.barrier_lifetime,
+        }
+    }
+}
+
+// Ages barriers and removes those that have decayed or been destroyed.
+pub fn update_barriers(barriers: &mut Vec<Barrier>, dt: f32) {
+    for barrier in barriers.iter_mut() {
+        barrier.life -= dt;
+    }
+    barriers.retain(|b| b.life > 0.0 && b.health > 0);
+}
+
+// Stops player bullets that hit a barrier, wearing the barrier down.
+pub fn block_bullets(bullets: &mut Vec<Bullet>, barriers: &mut [Barrier], bullet_size: Vec2) {
+    bullets.retain(|bullet| {
+        let bullet_rect = Rect::new(bullet.pos.x, bullet.pos.y, bullet_size.x, bullet_size.y);
+        match barriers.iter_mut().find(|b| b.health > 0 && b.rect.overlaps(&bullet_rect)) {
+            Some(barrier) => {
+                barrier.health -= 1;
+                false
+            }
+            None => true,
+        }
+    });
+}
+
+// Returns a position for a moving box that does not pass into any barrier, sliding along one axis if it can.
+// A box already overlapping a barrier may still move in any way that overlaps it less, so it can't get stuck.
+pub fn resolve_movement(prev: Vec2, next: Vec2, hitbox_of: impl Fn(Vec2) -> Rect, barriers: &[Barrier]) -> Vec2 {
+    let overlap = |pos: Vec2| -> f32 {
+        let hitbox = hitbox_of(pos);
+        barriers.iter().filter_map(|b| b.rect.intersect(hitbox)).map(|r| r.w * r.h).sum()
+    };
+    let current = overlap(prev);
+    [next, vec2(next.x, prev.y), vec2(prev.x, next.y)]
+        .into_iter()
+        .find(|&pos| {
+            let after = overlap(pos);
+            after == 0.0 || after < current
+        })
+        .unwrap_or(prev)
+}
+
+// Draws barriers, fading them out as they wear down and decay.
+pub fn draw_barriers(barriers: &[Barrier], config: &GameConfig) {
+    for barrier in barriers {
+        let strength = barrier.health as f32 / barrier.max_health as f32;
+        let fade = (barrier.life / config.barrier_lifetime).min(1.0);
+        let alpha = 0.4 + 0.5 * fade;
+        let r = barrier.rect;
+        draw_rectangle(r.x, r.y, r.w, r.h, Color::new(0.4, 0.7 * strength + 0.3, 1.0, alpha * 0.6));
+        draw_rectangle_lines(r.x, r.y, r.w, r.h, 2.0, Color::new(0.7, 0.9, 1.0, alpha));
+    }
+}
@@ -0,0 +1,234 @@
+use macroquad::prelude::*;
+use std::f32::consts::FRAC_PI_2;
+
+use crate::{get_hitbox, spawn_enemy_bullet, EnemyBullet, GameConfig};
+
+// Depth below the top of the playfield the boss descends to before it starts attacking.
+const BOSS_ENTRY_Y: f32 = 40.0;
+
+// Width of the sweeping laser's deadly beam, in pixels.
+const LASER_WIDTH: f32 = 14.0;
+
+// The boss's sweeping laser, which first marks where it will start and then turns a beam across the screen.
+struct LaserSweep {
+    // Direction of the beam in radians, measured the way screen angles turn, so straight down is a quarter turn.
+    angle: f32,
+    // Which way the beam turns, -1.0 or 1.0.
+    turn: f32,
+    // Seconds of warning left before the beam switches on.
+    telegraph: f32,
+    // Angle still left to sweep once the beam is on.
+    remaining: f32,
+}
+
+// One-off events from a boss update that the game should react to.
+#[derive(Default)]
+pub struct BossCues {
+    pub enraged: bool,
+    pub phase_changed: bool,
+}
+
+// A large enemy that strafes across the top of the screen, firing at the player.
+pub struct Boss {
+    pub pos: Vec2,
+    pub size: Vec2,
+    pub health: u32,
+    pub max_health: u32,
+    // Horizontal direction of travel, -1.0 or 1.0.
+    dir: f32,
+    fire_timer: f32,
+    // Seconds the fight has lasted since the boss arrived.
+    boss_timer: f32,
+    pub enraged: bool,
+    // Remaining time of the warning flash shown when the boss enrages.
+    pub enrage_flash: f32,
+    // How many health thresholds the boss has dropped past.
+    pub phase: usize,
+    // Remaining time of the flash shown when the boss enters a new phase.
+    pub phase_flash: f32,
+    // Seconds until the next laser sweep starts.
+    laser_timer: f32,
+    laser: Option<LaserSweep>,
+}
+
+impl Boss {
+    pub fn new(config: &GameConfig) -> Boss {
+        let size = vec2(160.0, 160.0);
+        let field = config.playfield();
+        Boss {
+            pos: vec2(field.center().x - size.x / 2.0, field.top() - size.y),
+            size,
+            health: config.boss_health,
+            max_health: config.boss_health,
+            dir: 1.0,
+            fire_timer: config.boss_fire_interval,
+            boss_timer: 0.0,
+            enraged: false,
+            enrage_flash: 0.0,
+            phase: 0,
+            phase_flash: 0.0,
+            laser_timer: config.boss_laser_interval,
+            laser: None,
+        }
+    }
+
+    // Returns the boss's collision box.
+    pub fn hitbox(&self) -> Rect {
+        get_hitbox(self.pos, self.size, 24.0)
+    }
+
+    // Returns the phase matching the boss's remaining health, counting thresholds passed.
+    fn phase_for_health(&self, config: &GameConfig) -> usize {
+        let fraction = self.health as f32 / self.max_health as f32;
+        config.boss_phase_thresholds.iter().filter(|&&t| fraction <= t).count()
+    }
+
+    // Moves and fires for one frame, reporting any enrage or phase change that happened.
+    pub fn update(&mut self, dt: f32, config: &GameConfig, enemy_bullets: &mut Vec<EnemyBullet>) -> BossCues {
+        let mut cues = BossCues::default();
+        self.enrage_flash = (self.enrage_flash - dt).max(0.0);
+        self.phase_flash = (self.phase_flash - dt).max(0.0);
+
+        // Fly in from the top before the fight starts.
+        let field = config.playfield();
+        if self.pos.y < field.top() + BOSS_ENTRY_Y {
+            self.pos.y = (self.pos.y + config.boss_speed * dt).min(field.top() + BOSS_ENTRY_Y);
+            return cues;
+        }
+
+        // Escalate as health drops past each threshold.
+        let phase = self.phase_for_health(config);
+        if phase > self.phase {
+            self.phase = phase;
+            self.phase_flash = 0.6;
+            cues.phase_changed = true;
+        }
+
+        // Enrage once the fight has dragged on past the time limit.
+        self.boss_timer += dt;
+        if !self.enraged && self.boss_timer >= config.boss_enrage_time {
+            self.enraged = true;
+            self.enrage_flash = 1.0;
+            cues.enraged = true;
+        }
+        let phase_scale = 1.0 + self.phase as f32 * config.boss_phase_speed_step;
+        let (speed, fire_interval) = if self.enraged {
+            (config.boss_speed * config.boss_enrage_speed_multiplier, config.boss_fire_interval / config.boss_enrage_fire_multiplier)
+        } else {
+            (config.boss_speed, config.boss_fire_interval)
+        };
+        let (speed, fire_interval) = (speed * phase_scale, fire_interval / phase_scale);
+
+        // Hold still and stop firing while a laser sweep is warming up or running.
+        if self.update_laser(dt, config) {
+            return cues;
+        }
+
+        // Strafe from side to side, turning at the edges of the playfield.
+        self.pos.x += self.dir * speed * dt;
+        if self.pos.x < field.left() { self.pos.x = field.left(); self.dir = 1.0; }
+        if self.pos.x > field.right() - self.size.x { self.pos.x = field.right() - self.size.x; self.dir = -1.0; }
+
+        // Fire straight down, widening the spread with each phase and again when enraged.
+        self.fire_timer -= dt;
+        if self.fire_timer <= 0.0 {
+            self.fire_timer = fire_interval;
+            let origin = vec2(self.pos.x + self.size.x / 2.0, self.pos.y + self.size.y);
+            let spread = (self.phase + self.enraged as usize) as i32;
+            for i in -spread..=spread {
+                let angle = i as f32 * 0.2;
+                let vel = vec2(angle.sin(), angle.cos()) * config.boss_bullet_speed;
+                spawn_enemy_bullet(enemy_bullets, EnemyBullet { pos: origin, vel, gravity: 0.0 }, config.max_enemy_bullets);
+            }
+        }
+        cues
+    }
+
+    // Advances the laser sweep, starting a new one when its timer runs out, and returns whether one is active.
+    // Each sweep starts off to one side of straight down and turns across to the other.
+    fn update_laser(&mut self, dt: f32, config: &GameConfig) -> bool {
+        if self.laser.is_none() {
+            self.laser_timer -= dt;
+            if self.laser_timer > 0.0 {
+                return false;
+            }
+            self.laser_timer = config.boss_laser_interval;
+            let turn = if rand::gen_range(0, 2) == 0 { -1.0 } else { 1.0 };
+            self.laser = Some(LaserSweep {
+                angle: FRAC_PI_2 - turn * config.boss_laser_sweep_arc / 2.0,
+                turn,
+                telegraph: config.boss_laser_telegraph,
+                remaining: config.boss_laser_sweep_arc,
+            });
+        }
+        let Some(laser) = self.laser.as_mut() else { return false };
+        if laser.telegraph > 0.0 {
+            laser.telegraph -= dt;
+            return true;
+        }
+        let step = (config.boss_laser_sweep_speed * dt).min(laser.remaining);
+        laser.angle += laser.turn * step;
+        laser.remaining -= step;
+        if laser.remaining <= 0.0 {
+            self.laser = None;
+        }
+        true
+    }
+
+    // Returns the two ends of the laser beam, from under the boss to well past the edge of the screen.
+    fn laser_segment(&self, laser: &LaserSweep) -> (Vec2, Vec2) {
+        let origin = vec2(self.pos.x + self.size.x / 2.0, self.pos.y + self.size.y * 0.75);
+        let length = vec2(screen_width(), screen_height()).length();
+        (origin, origin + Vec2::from_angle(laser.angle) * length)
+    }
+
+    // Returns whether the live laser beam touches a circle of radius `reach` around `point`.
+    // The warning line shown beforehand is harmless.
+    pub fn laser_hits(&self, point: Vec2, reach: f32) -> bool {
+        let Some(laser) = self.laser.as_ref().filter(|l| l.telegraph <= 0.0) else { return false };
+        let (start, end) = self.laser_segment(laser);
+        let along = end - start;
+        let t = ((point - start).dot(along) / along.length_squared()).clamp(0.0, 1.0);
+        point.distance(start + along * t) <= LASER_WIDTH / 2.0 + reach
+    }
+
+    // Returns the ends of the laser beam and whether it is still only a warning, while a sweep is under way.
+    pub fn laser_beam(&self) -> Option<(Vec2, Vec2, bool)> {
+        let laser = self.laser.as_ref()?;
+        let (start, end) = self.laser_segment(laser);
+        Some((start, end, laser.telegraph > 0.0))
+    }
+
+    // Draws the boss's hull, tinted by its phase and enrage.
+    pub fn draw(&self, texture: &Texture2D) {
+        // Each phase shifts the hull further toward red; enraging makes it pulse.
+        let warmth = 1.0 - self.phase as f32 * 0.2;
+        let tint = if self.enraged {
+            let pulse = (get_time() as f32 * 8.0).sin() * 0.5 + 0.5;
+            Color::new(1.0, (0.4 + 0.3 * pulse) * warmth, (0.4 + 0.3 * pulse) * warmth, 1.0)
+        } else {
+            Color::new(1.0, warmth, warmth * warmth, 1.0)
+        };
+        draw_texture_ex(texture, self.pos.x, self.pos.y, tint, DrawTextureParams { dest_size: Some(self.size), ..Default::default() });
+    }
+
+    // Draws the health bar along the top of the screen, in screen space.
+    pub fn draw_health_bar(&self) {
+        let bar_width = screen_width() * 0.4;
+        let bar_x = screen_width() / 2.0 - bar_width / 2.0;
+        let fraction = self.health as f32 / self.max_health as f32;
+        draw_rectangle(bar_x, 12.0, bar_width, 10.0, DARKGRAY);
+        draw_rectangle(bar_x, 12.0, bar_width * fraction, 10.0, if self.enraged { RED } else { ORANGE });
+    }
+}
+
+// Draws the thin warning line of a telegraphed laser sweep, or the beam itself once it is live.
+pub fn draw_laser_beam(start: Vec2, end: Vec2, telegraphed: bool) {
+    if telegraphed {
+        let blink = (get_time() as f32 * 12.0).sin() * 0.25 + 0.6;
+        draw_line(start.x, start.y, end.x, end.y, 2.0, Color::new(1.0, 0.2, 0.2, blink));
+    } else {
+        draw_line(start.x, start.y, end.x, end.y, LASER_WIDTH, Color::new(1.0, 0.2, 0.3, 0.85));
+        draw_line(start.x, start.y, end.x, end.y, LASER_WIDTH / 3.0, Color::new(1.0, 0.9, 0.9, 1.0));
+    }
+}
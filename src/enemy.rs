@@ -0,0 +1,411 @@
+use macroquad::prelude::*;
+
+use crate::barrier::Barrier;
+use crate::formation::FormationSlot;
+use crate::gravity_well::GravityWell;
+use crate::powerup::{draw_powerup_shape, PowerUp, POWERUP_COLOR, POWERUP_SIZE};
+use crate::shockwave::Shockwave;
+use crate::{get_hitbox, spawn_enemy_bullet, EnemyBullet, GameConfig};
+
+// Fraction of the normal enemy speed a shocker moves at.
+const SHOCKER_SPEED_FACTOR: f32 = 0.6;
+// Number of faded copies drawn behind a fast-moving enemy.
+const TRAIL_LENGTH: usize = 3;
+// Seconds between recorded trail positions.
+const TRAIL_SAMPLE_INTERVAL: f32 = 0.03;
+
+// An enemy ship and the state its behaviour needs.
+pub struct Enemy {
+    pub pos: Vec2,
+    pub kind: EnemyKind,
+    pub health: u32,
+    pub max_health: u32,
+    // Falling speed before the difficulty multiplier is applied.
+    pub speed: f32,
+    // Counts down to the enemy's next special action.
+    pub ability_timer: f32,
+    // Remaining time of the enemy's ability visual.
+    pub effect_timer: f32,
+    // Remaining time of the glow shown after being healed.
+    pub heal_flash: f32,
+    // Recent positions, newest first, used to draw a motion trail.
+    trail: Vec<Vec2>,
+    trail_timer: f32,
+    // Seconds since the enemy spawned.
+    age: f32,
+    // Set for enemies that move as part of a formation.
+    pub formation: Option<FormationSlot>,
+    // Place in the kill order of a numbered formation, counted from 0.
+    pub sequence: Option<usize>,
+    // Set on a thief that has grabbed a pickup and is making off with it.
+    pub carrying: bool,
+}
+
+// The different behaviours an enemy can have.
+#[derive(Clone, Copy, PartialEq)]
+pub enum EnemyKind {
+    Basic,
+    // Periodically restores health to damaged enemies around it.
+    Healer,
+    // Falls much faster than other enemies.
+    Fast,
+    // Speeds up the closer it gets to the player's height, as if committing to a ram.
+    Rammer,
+    // A stationary target for practice mode that never dies.
+    Dummy,
+    // Drops stationary barriers that block the player's shots and movement.
+    Builder,
+    // Hangs back and lobs arcing shots that land where the player is.
+    Lobber,
+    // Looks like a falling pickup to lure the player in, but is deadly to touch.
+    Decoy,
+    // Stops partway down the screen, charges up and releases an expanding shockwave ring.
+    Shocker,
+    // Flies to a falling pickup, grabs it and makes off with it unless shot down, which drops it again.
+    Thief,
+    // Opens a short-lived gravity well once it reaches a set depth, then carries on down the screen.
+    Warper,
+}
+
+impl EnemyKind {
+    // Returns the falling speed an enemy of this kind spawns with, before the difficulty multiplier.
+    pub fn base_speed(self, config: &GameConfig) -> f32 {
+        match self {
+            EnemyKind::Basic | EnemyKind::Healer | EnemyKind::Rammer => config.enemy_speed,
+            EnemyKind::Fast => config.enemy_speed * config.fast_speed_multiplier,
+            EnemyKind::Dummy => 0.0,
+            EnemyKind::Builder => config.enemy_speed * 0.8,
+            EnemyKind::Lobber | EnemyKind::Warper => config.enemy_speed * 0.5,
+            EnemyKind::Decoy => config.powerup_fall_speed,
+            EnemyKind::Shocker => config.enemy_speed * SHOCKER_SPEED_FACTOR,
+            EnemyKind::Thief => config.enemy_speed * 0.4,
+        }
+    }
+}
+
+impl Enemy {
+    pub fn new(kind: EnemyKind, pos: Vec2, config: &GameConfig) -> Enemy {
+        let (health, ability_timer) = match kind {
+            EnemyKind::Basic | EnemyKind::Fast | EnemyKind::Rammer | EnemyKind::Dummy | EnemyKind::Decoy => (1, 0.0),
+            EnemyKind::Healer => (config.healer_health, config.healer_interval),
+            EnemyKind::Builder => (config.builder_health, config.builder_drop_interval),
+            EnemyKind::Lobber => (1, config.lobber_fire_interval / 2.0),
+            EnemyKind::Shocker => (2, config.shockwave_charge_time),
+            EnemyKind::Thief => (2, 0.0),
+            // The ability timer stays positive until the warper has opened its well.
+            EnemyKind::Warper => (2, 1.0),
+        };
+        let speed = kind.base_speed(config);
+        Enemy {
+            pos,
+            kind,
+            health,
+            max_health: health,
+            speed,
+            ability_timer,
+            effect_timer: 0.0,
+            heal_flash: 0.0,
+            trail: vec![],
+            trail_timer: 0.0,
+            age: 0.0,
+            formation: None,
+            sequence: None,
+            carrying: false,
+        }
+    }
+
+    // Moves the enemy down, recording a trail while it travels faster than the threshold.
+    pub fn update_movement(&mut self, speed_multiplier: f32, player_y: f32, config: &GameConfig, dt: f32) {
+        if self.kind == EnemyKind::Rammer {
+            // Accelerate harder as the vertical gap to the player closes, up to a dodgeable cap.
+            // The cap is on the base speed, so it rises with the difficulty like every other enemy's speed.
+            let closeness = 1.0 - ((player_y - self.pos.y) / config.playfield().h).clamp(0.0, 1.0);
+            self.speed = (self.speed + config.rammer_acceleration * closeness * closeness * dt).min(config.rammer_max_speed);
+        }
+        let speed = self.speed * speed_multiplier;
+        if speed < config.trail_speed_threshold {
+            self.trail.clear();
+        } else {
+            self.trail_timer -= dt;
+            if self.trail_timer <= 0.0 {
+                self.trail_timer = TRAIL_SAMPLE_INTERVAL;
+                self.trail.insert(0, self.pos);
+                self.trail.truncate(TRAIL_LENGTH);
+            }
+        }
+        self.age += dt;
+        self.pos.y += speed * dt;
+        if let Some(slot) = self.formation {
+            self.pos.x = slot.x_at(self.age, config);
+        }
+    }
+
+    // Returns the color the enemy's sprite is tinted with, flashing green while it is being healed.
+    pub fn tint(&self) -> Color {
+        match self.kind {
+            _ if self.heal_flash > 0.0 => Color::new(0.6, 1.0, 0.6, 1.0),
+            EnemyKind::Healer => Color::new(0.5, 1.0, 0.7, 1.0),
+            EnemyKind::Fast => Color::new(1.0, 0.8, 0.5, 1.0),
+            EnemyKind::Rammer => Color::new(1.0, 0.5, 0.5, 1.0),
+            EnemyKind::Dummy => Color::new(0.7, 0.7, 1.0, 1.0),
+            EnemyKind::Builder => Color::new(0.6, 0.8, 1.0, 1.0),
+            EnemyKind::Lobber => Color::new(1.0, 0.8, 0.3, 1.0),
+            EnemyKind::Shocker => Color::new(0.6, 0.9, 1.0, 1.0),
+            EnemyKind::Thief => Color::new(0.8, 0.5, 1.0, 1.0),
+            EnemyKind::Warper => Color::new(0.6, 0.4, 0.9, 1.0),
+            EnemyKind::Basic | EnemyKind::Decoy => WHITE,
+        }
+    }
+
+    // Returns the enemy's collision box. A decoy's matches the small pickup it is drawn as.
+    pub fn hitbox(&self, enemy_size: Vec2) -> Rect {
+        match self.kind {
+            EnemyKind::Decoy => {
+                let center = self.pos + enemy_size / 2.0;
+                Rect::new(center.x - POWERUP_SIZE / 2.0, center.y - POWERUP_SIZE / 2.0, POWERUP_SIZE, POWERUP_SIZE)
+            }
+            _ => get_hitbox(self.pos, enemy_size, 8.0),
+        }
+    }
+
+    // Returns whether the enemy is wholly past a side of `field` and still heading away from it.
+    // Side formations start out past the edge they enter from, so only the edge they drift toward counts.
+    pub fn left_sideways(&self, enemy_size: Vec2, field: Rect) -> bool {
+        let drift = self.formation.map_or(0.0, |s| s.drift);
+        (self.pos.x + enemy_size.x <= field.left() && drift <= 0.0) || (self.pos.x >= field.right() && drift >= 0.0)
+    }
+}
+
+// Returns the points a kill is worth, scaled by how tough and fast the enemy is and whether it has an ability.
+// Callers pass the kind's base speed, so a kill scores the same whenever it happens.
+pub fn enemy_points(kind: EnemyKind, health: u32, speed: f32, config: &GameConfig) -> u32 {
+    let has_ability = matches!(kind, EnemyKind::Healer | EnemyKind::Builder | EnemyKind::Lobber | EnemyKind::Shocker | EnemyKind::Thief | EnemyKind::Warper);
+    let value = config.points_base
+        + config.points_per_health * health.saturating_sub(1) as f32
+        + config.points_per_speed * (speed / config.enemy_speed - 1.0).max(0.0)
+        + if has_ability { config.points_ability_bonus } else { 0.0 };
+    (value.round() as u32).max(1)
+}
+
+// Returns the glow a decoy is drawn in: the pickup color shifted toward a warning red by the configured tell.
+pub fn decoy_color(config: &GameConfig) -> Color {
+    let tell = config.decoy_tell.clamp(0.0, 1.0);
+    Color::new(
+        POWERUP_COLOR.r + (1.0 - POWERUP_COLOR.r) * tell,
+        POWERUP_COLOR.g * (1.0 - tell * 0.6),
+        POWERUP_COLOR.b * (1.0 - tell * 0.6),
+        1.0,
+    )
+}
+
+// Returns what is wrong with a table of spawn weights, or None if it can be sampled from.
+pub fn enemy_weights_problem(weights: &[(EnemyKind, f32)]) -> Option<String> {
+    if let Some((_, weight)) = weights.iter().find(|(_, w)| !w.is_finite() || *w < 0.0) {
+        return Some(format!("weight {} is not a non-negative number", weight));
+    }
+    let total: f32 = weights.iter().map(|(_, w)| w).sum();
+    if total <= 0.0 {
+        return Some("weights add up to nothing".to_string());
+    }
+    None
+}
+
+// Picks the kind of a newly spawned enemy in proportion to the given weights, falling back to a basic enemy
+// when the table is empty or unusable.
+pub fn pick_enemy_kind(weights: &[(EnemyKind, f32)]) -> EnemyKind {
+    if enemy_weights_problem(weights).is_some() {
+        return EnemyKind::Basic;
+    }
+    let total: f32 = weights.iter().map(|(_, w)| w).sum();
+    let mut roll = rand::gen_range(0.0, total);
+    for &(kind, weight) in weights {
+        if roll < weight {
+            return kind;
+        }
+        roll -= weight;
+    }
+    EnemyKind::Basic
+}
+
+// Runs each healer's pulse, topping up the health of damaged enemies within its radius.
+pub fn update_healers(enemies: &mut [Enemy], enemy_size: Vec2, config: &GameConfig, dt: f32) {
+    let mut pulses = vec![];
+    for (i, enemy) in enemies.iter_mut().enumerate() {
+        enemy.heal_flash = (enemy.heal_flash - dt).max(0.0);
+        enemy.effect_timer = (enemy.effect_timer - dt).max(0.0);
+        if enemy.kind != EnemyKind::Healer { continue; }
+        enemy.ability_timer -= dt;
+        if enemy.ability_timer <= 0.0 {
+            enemy.ability_timer = config.healer_interval;
+            enemy.effect_timer = 0.5;
+            pulses.push((i, enemy.pos + enemy_size / 2.0));
+        }
+    }
+    for (healer_index, center) in pulses {
+        for (i, enemy) in enemies.iter_mut().enumerate() {
+            let in_range = (enemy.pos + enemy_size / 2.0).distance(center) <= config.healer_radius;
+            if i != healer_index && in_range && enemy.health < enemy.max_health {
+                enemy.health = (enemy.health + config.healer_amount).min(enemy.max_health);
+                enemy.heal_flash = 0.5;
+            }
+        }
+    }
+}
+
+// Counts down each builder's timer and returns the barriers dropped this frame.
+// A builder over the player holds its barrier until it has moved clear, so one never lands on the ship.
+pub fn update_builders(enemies: &mut [Enemy], enemy_size: Vec2, player_hitbox: Rect, config: &GameConfig, dt: f32) -> Vec<Barrier> {
+    let mut dropped = vec![];
+    for builder in enemies.iter_mut().filter(|e| e.kind == EnemyKind::Builder) {
+        builder.ability_timer -= dt;
+        if builder.ability_timer <= 0.0 {
+            let barrier = Barrier::new(builder.pos + vec2(enemy_size.x / 2.0, enemy_size.y), config);
+            if barrier.rect.overlaps(&player_hitbox) { continue; }
+            builder.ability_timer = config.builder_drop_interval;
+            dropped.push(barrier);
+        }
+    }
+    dropped
+}
+
+// Fires each lobber's arcing shot at the target once it is on screen and above it.
+pub fn update_lobbers(enemies: &mut [Enemy], enemy_size: Vec2, target: Vec2, config: &GameConfig, enemy_bullets: &mut Vec<EnemyBullet>, dt: f32) {
+    for lobber in enemies.iter_mut().filter(|e| e.kind == EnemyKind::Lobber) {
+        lobber.ability_timer -= dt;
+        let origin = lobber.pos + vec2(enemy_size.x / 2.0, enemy_size.y);
+        if lobber.ability_timer <= 0.0 && origin.y > 0.0 && origin.y < target.y {
+            lobber.ability_timer = config.lobber_fire_interval;
+            let vel = lob_velocity(origin, target, config.lobber_launch_speed, config.lob_gravity);
+            spawn_enemy_bullet(enemy_bullets, EnemyBullet { pos: origin, vel, gravity: config.lob_gravity }, config.max_enemy_bullets);
+        }
+    }
+}
+
+// Returns the launch velocity for a shot thrown upward at `launch_speed` that comes down on `target` under `gravity`.
+fn lob_velocity(origin: Vec2, target: Vec2, launch_speed: f32, gravity: f32) -> Vec2 {
+    let drop = target.y - origin.y;
+    let flight_time = (launch_speed + (launch_speed * launch_speed + 2.0 * gravity * drop).max(0.0).sqrt()) / gravity;
+    vec2((target.x - origin.x) / flight_time, -launch_speed)
+}
+
+// Holds each shocker at the trigger depth while it charges, returning the rings released this frame.
+// A shocker only ever fires once, then carries on down the screen.
+pub fn update_shockers(enemies: &mut [Enemy], enemy_size: Vec2, config: &GameConfig, dt: f32) -> Vec<Shockwave> {
+    let mut released = vec![];
+    for shocker in enemies.iter_mut().filter(|e| e.kind == EnemyKind::Shocker && e.ability_timer > 0.0) {
+        let center = shocker.pos + enemy_size / 2.0;
+        let field = config.playfield();
+        if center.y < field.top() + field.h * config.shockwave_trigger_depth { continue; }
+        shocker.speed = 0.0;
+        shocker.ability_timer -= dt;
+        if shocker.ability_timer <= 0.0 {
+            shocker.speed = config.enemy_speed * SHOCKER_SPEED_FACTOR;
+            released.push(Shockwave::new(center));
+        }
+    }
+    released
+}
+
+// Opens a gravity well where each warper crosses the trigger depth, returning the wells opened this frame.
+// A warper only ever opens one.
+pub fn update_warpers(enemies: &mut [Enemy], enemy_size: Vec2, config: &GameConfig) -> Vec<GravityWell> {
+    let mut opened = vec![];
+    for warper in enemies.iter_mut().filter(|e| e.kind == EnemyKind::Warper && e.ability_timer > 0.0 && e.health > 0) {
+        let center = warper.pos + enemy_size / 2.0;
+        let field = config.playfield();
+        if center.y >= field.top() + field.h * config.gravity_well_trigger_depth {
+            warper.ability_timer = 0.0;
+            opened.push(GravityWell::new(center, config));
+        }
+    }
+    opened
+}
+
+// Steers each empty-handed thief toward the nearest pickup and lets it grab one it reaches.
+// A thief carrying a pickup dives for the bottom of the screen to get away with it.
+pub fn update_thieves(enemies: &mut [Enemy], enemy_size: Vec2, powerups: &mut Vec<PowerUp>, config: &GameConfig, dt: f32) {
+    for thief in enemies.iter_mut().filter(|e| e.kind == EnemyKind::Thief && e.health > 0) {
+        if thief.carrying {
+            thief.pos.y += config.thief_speed * dt;
+            continue;
+        }
+        let center = thief.pos + enemy_size / 2.0;
+        let Some(target) = powerups.iter().map(|p| p.pos).min_by(|a, b| a.distance(center).total_cmp(&b.distance(center))) else { continue };
+        let step = (config.thief_speed * dt).min(target.distance(center));
+        thief.pos += (target - center).normalize_or_zero() * step;
+        let hitbox = thief.hitbox(enemy_size);
+        if let Some(index) = powerups.iter().position(|p| p.hitbox().overlaps(&hitbox)) {
+            powerups.swap_remove(index);
+            thief.carrying = true;
+        }
+    }
+}
+
+// Makes every thief let go of the pickup it is carrying, dropping it where the thief is.
+// Used when enemies are wiped out without being shot, so the clear costs the player nothing they earned.
+pub fn release_carried(enemies: &[Enemy], enemy_size: Vec2, powerups: &mut Vec<PowerUp>) {
+    powerups.extend(enemies.iter().filter(|e| e.carrying).map(|e| PowerUp { pos: e.pos + vec2(enemy_size.x / 2.0, enemy_size.y) }));
+}
+
+// Draws the faded copies left behind by fast-moving enemies.
+pub fn draw_enemy_trails(enemies: &[Enemy], enemy_size: Vec2, texture: &Texture2D) {
+    for enemy in enemies {
+        for (i, pos) in enemy.trail.iter().enumerate() {
+            let alpha = 0.3 * (1.0 - i as f32 / TRAIL_LENGTH as f32);
+            draw_texture_ex(texture, pos.x, pos.y, Color::new(1.0, 1.0, 1.0, alpha), DrawTextureParams { dest_size: Some(enemy_size), ..Default::default() });
+        }
+    }
+}
+
+// Draws healing pulses, links from healers to the enemies they just healed, and damaged enemies' health.
+pub fn draw_enemy_effects(enemies: &[Enemy], enemy_size: Vec2, config: &GameConfig) {
+    for thief in enemies.iter().filter(|e| e.carrying) {
+        draw_powerup_shape(thief.pos + vec2(enemy_size.x / 2.0, enemy_size.y), POWERUP_COLOR);
+    }
+    for shocker in enemies.iter().filter(|e| e.kind == EnemyKind::Shocker && e.speed == 0.0) {
+        let center = shocker.pos + enemy_size / 2.0;
+        let charge = 1.0 - shocker.ability_timer / config.shockwave_charge_time;
+        draw_circle(center.x, center.y, enemy_size.x * 0.6 * charge, Color::new(0.6, 0.9, 1.0, 0.4 * charge));
+    }
+    for healer in enemies.iter().filter(|e| e.kind == EnemyKind::Healer && e.effect_timer > 0.0) {
+        let center = healer.pos + enemy_size / 2.0;
+        let progress = 1.0 - healer.effect_timer / 0.5;
+        draw_circle_lines(center.x, center.y, config.healer_radius * progress, 2.0, Color::new(0.3, 1.0, 0.4, 1.0 - progress));
+        for target in enemies.iter().filter(|e| e.heal_flash > 0.0) {
+            let target_center = target.pos + enemy_size / 2.0;
+            if target_center.distance(center) <= config.healer_radius {
+                draw_line(center.x, center.y, target_center.x, target_center.y, 1.5, Color::new(0.3, 1.0, 0.4, 0.4));
+            }
+        }
+    }
+    for enemy in enemies.iter().filter(|e| e.health < e.max_health) {
+        let fraction = enemy.health as f32 / enemy.max_health as f32;
+        draw_rectangle(enemy.pos.x, enemy.pos.y - 6.0, enemy_size.x, 4.0, DARKGRAY);
+        draw_rectangle(enemy.pos.x, enemy.pos.y - 6.0, enemy_size.x * fraction, 4.0, GREEN);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn basic_enemy_is_worth_the_base_value() {
+        let config = GameConfig::default();
+        assert_eq!(enemy_points(EnemyKind::Basic, 1, config.enemy_speed, &config), 1);
+    }
+
+    #[test]
+    fn points_grow_with_health_speed_and_ability() {
+        let config = GameConfig::default();
+        assert_eq!(enemy_points(EnemyKind::Basic, 3, config.enemy_speed, &config), 3);
+        assert_eq!(enemy_points(EnemyKind::Basic, 1, config.enemy_speed * 2.0, &config), 2);
+        assert_eq!(enemy_points(EnemyKind::Healer, 3, config.enemy_speed, &config), 4);
+    }
+
+    #[test]
+    fn slow_enemies_are_not_worth_less_than_one() {
+        let config = GameConfig { points_base: 0.0, ..GameConfig::default() };
+        assert_eq!(enemy_points(EnemyKind::Basic, 1, config.enemy_speed * 0.5, &config), 1);
+    }
+}
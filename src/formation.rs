@@ -0,0 +1,128 @@
+use macroquad::prelude::*;
+use macroquad::rand::ChooseRandom;
+
+use crate::enemy::{Enemy, EnemyKind};
+use crate::GameConfig;
+
+// An enemy's place in a formation, used to keep its spacing while the group moves.
+#[derive(Clone, Copy)]
+pub struct FormationSlot {
+    // Horizontal position the enemy sways around.
+    pub anchor_x: f32,
+    // Position of the enemy along the line, counted from the left.
+    pub index: usize,
+    // Horizontal speed of the whole formation, non-zero for groups that enter from a side.
+    pub drift: f32,
+}
+
+// A screen edge a formation can sweep in from.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Edge {
+    Left,
+    Right,
+}
+
+impl FormationSlot {
+    // Returns the enemy's x position after `age` seconds, swaying on the formation's shared sine wave.
+    pub fn x_at(&self, age: f32, config: &GameConfig) -> f32 {
+        let phase = age / config.formation_period * std::f32::consts::TAU + self.index as f32 * config.formation_index_phase;
+        self.anchor_x + self.drift * age + config.formation_amplitude * phase.sin()
+    }
+
+    // Returns the edge the formation enters from, if it sweeps in sideways.
+    pub fn entry_edge(&self) -> Option<Edge> {
+        match self.drift {
+            d if d > 0.0 => Some(Edge::Left),
+            d if d < 0.0 => Some(Edge::Right),
+            _ => None,
+        }
+    }
+}
+
+// Builds a horizontal line of enemies, centered on the playfield, that sweeps in a sine wave as it descends.
+pub fn sine_formation(config: &GameConfig, enemy_size: Vec2) -> Vec<Enemy> {
+    let count = config.formation_size;
+    let width = (count.saturating_sub(1)) as f32 * config.formation_spacing + enemy_size.x;
+    let field = config.playfield();
+    let start_x = field.center().x - width / 2.0;
+    (0..count)
+        .map(|index| {
+            let slot = FormationSlot { anchor_x: start_x + index as f32 * config.formation_spacing, index, drift: 0.0 };
+            let mut enemy = Enemy::new(EnemyKind::Basic, vec2(slot.x_at(0.0, config), field.top() - enemy_size.y), config);
+            enemy.formation = Some(slot);
+            enemy
+        })
+        .collect()
+}
+
+// Builds a line of enemies that sweeps in from one side of the screen, drifting slowly down as it crosses.
+pub fn side_formation(config: &GameConfig, enemy_size: Vec2, edge: Edge) -> Vec<Enemy> {
+    // Count every offset outward from the entry edge, so the leading enemy arrives first.
+    let field = config.playfield();
+    let (edge_x, outward, drift) = match edge {
+        Edge::Left => (field.left() - enemy_size.x - config.formation_amplitude, -1.0, config.formation_side_speed),
+        Edge::Right => (field.right() + config.formation_amplitude, 1.0, -config.formation_side_speed),
+    };
+    let y = field.top() + field.h * config.formation_side_height;
+    (0..config.formation_size)
+        .map(|index| {
+            let slot = FormationSlot { anchor_x: edge_x + outward * index as f32 * config.formation_spacing, index, drift };
+            let mut enemy = Enemy::new(EnemyKind::Basic, vec2(slot.x_at(0.0, config), y), config);
+            enemy.speed *= config.formation_side_descent;
+            enemy.formation = Some(slot);
+            enemy
+        })
+        .collect()
+}
+
+// Draws a pulsing arrow on the given edge of `field` at height `y`, sized by how many enemies are about to enter there.
+pub fn draw_entry_arrow(edge: Edge, y: f32, count: usize, field: Rect) {
+    let pulse = (get_time() as f32 * 10.0).sin() * 0.15 + 1.0;
+    let half_height = (20.0 + 6.0 * count as f32) * pulse;
+    let depth = half_height * 1.2;
+    let (tip_x, base_x) = match edge {
+        Edge::Left => (field.left() + 8.0 + depth, field.left() + 8.0),
+        Edge::Right => (field.right() - 8.0 - depth, field.right() - 8.0),
+    };
+    let color = Color::new(1.0, 0.3, 0.2, 0.5 + 0.3 * (pulse - 0.85) / 0.3);
+    draw_triangle(vec2(base_x, y - half_height), vec2(base_x, y + half_height), vec2(tip_x, y), color);
+}
+
+// Builds a sine formation whose enemies carry a shuffled kill order, shown as numbers on them.
+pub fn numbered_formation(config: &GameConfig, enemy_size: Vec2) -> Vec<Enemy> {
+    let mut order: Vec<usize> = (0..config.formation_size).collect();
+    order.shuffle();
+    let mut enemies = sine_formation(config, enemy_size);
+    for (enemy, number) in enemies.iter_mut().zip(order) {
+        enemy.sequence = Some(number);
+    }
+    enemies
+}
+
+// What a kill from a numbered formation did to its sequence.
+pub enum KillOrderResult {
+    Progress,
+    Complete,
+    Broken,
+}
+
+// Tracks which enemy of a numbered formation has to be destroyed next.
+pub struct KillOrder {
+    next: usize,
+    size: usize,
+}
+
+impl KillOrder {
+    pub fn new(size: usize) -> KillOrder {
+        KillOrder { next: 0, size }
+    }
+
+    // Checks the kill of the enemy numbered `number` against the expected order.
+    pub fn record(&mut self, number: usize) -> KillOrderResult {
+        if number != self.next {
+            return KillOrderResult::Broken;
+        }
+        self.next += 1;
+        if self.next == self.size { KillOrderResult::Complete } else { KillOrderResult::Progress }
+    }
+}
@@ -0,0 +1,58 @@
+use macroquad::prelude::*;
+use std::f32::consts::TAU;
+
+use crate::GameConfig;
+
+// A short-lived point that drags the player and bullets toward it, released by a warper.
+#[derive(Clone)]
+pub struct GravityWell {
+    pub center: Vec2,
+    // Seconds left before the well collapses.
+    remaining: f32,
+}
+
+impl GravityWell {
+    pub fn new(center: Vec2, config: &GameConfig) -> GravityWell {
+        GravityWell { center, remaining: config.gravity_well_duration }
+    }
+}
+
+// Runs down every well and drops those that have collapsed.
+pub fn update_gravity_wells(wells: &mut Vec<GravityWell>, dt: f32) {
+    for well in wells.iter_mut() {
+        well.remaining -= dt;
+    }
+    wells.retain(|w| w.remaining > 0.0);
+}
+
+// Returns the combined pull on `point`, as a direction whose length grows toward 1.0 at a well's center
+// and fades to nothing at its radius. The total never exceeds 1.0, so overlapping wells are no stronger.
+pub fn gravity_pull(wells: &[GravityWell], point: Vec2, config: &GameConfig) -> Vec2 {
+    let pull: Vec2 = wells
+        .iter()
+        .map(|w| {
+            let offset = w.center - point;
+            let falloff = 1.0 - (offset.length() / config.gravity_well_radius).min(1.0);
+            offset.normalize_or_zero() * falloff
+        })
+        .sum();
+    pull.clamp_length_max(1.0)
+}
+
+// Draws each well as arms spiraling in toward its center, fading as it collapses.
+pub fn draw_gravity_wells(wells: &[GravityWell], config: &GameConfig) {
+    let spin = get_time() as f32 * 3.0;
+    for well in wells {
+        let alpha = (well.remaining / config.gravity_well_duration).min(1.0);
+        draw_circle_lines(well.center.x, well.center.y, config.gravity_well_radius, 1.0, Color::new(0.7, 0.4, 1.0, 0.25 * alpha));
+        for ring in 1..=4 {
+            let radius = config.gravity_well_radius * ring as f32 / 5.0;
+            // Inner rings turn faster, so the arms look like they are being drawn in.
+            let rotation = (spin * (5 - ring) as f32 + ring as f32).rem_euclid(TAU).to_degrees();
+            for arm in 0..3 {
+                draw_arc(well.center.x, well.center.y, 32, radius, rotation + arm as f32 * 120.0, 2.0, 70.0, Color::new(0.7, 0.4, 1.0, 0.6 * alpha));
+            }
+        }
+        draw_circle(well.center.x, well.center.y, 8.0, Color::new(0.2, 0.0, 0.35, alpha));
+    }
+}
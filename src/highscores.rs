@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+
+use crate::{Difficulty, GameMode};
+
+// Best scores for each mode and difficulty, saved as one "mode difficulty score" line per entry,
+// followed by "flawless" when that run reached a score milestone without losing a life.
+pub struct HighScores {
+    path: String,
+    scores: HashMap<(GameMode, Difficulty), (u32, bool)>,
+}
+
+impl HighScores {
+    // Reads saved scores from the given file, starting empty if it is missing or unreadable.
+    pub fn load(path: &str) -> HighScores {
+        let mut scores = HashMap::new();
+        if let Ok(contents) = std::fs::read_to_string(path) {
+            for line in contents.lines() {
+                let mut parts = line.split_whitespace();
+                let entry = (
+                    parts.next().and_then(GameMode::from_name),
+                    parts.next().and_then(Difficulty::from_name),
+                    parts.next().and_then(|s| s.parse::<u32>().ok()),
+                );
+                let flawless = parts.next() == Some("flawless");
+                if let (Some(mode), Some(difficulty), Some(score)) = entry {
+                    scores.insert((mode, difficulty), (score, flawless));
+                }
+            }
+        }
+        HighScores { path: path.to_string(), scores }
+    }
+
+    // Returns the best score for a mode and difficulty, or 0 if none has been set.
+    pub fn get(&self, mode: GameMode, difficulty: Difficulty) -> u32 {
+        self.scores.get(&(mode, difficulty)).map_or(0, |&(score, _)| score)
+    }
+
+    // Returns whether the run that set the best score for a mode and difficulty earned the flawless badge.
+    pub fn is_flawless(&self, mode: GameMode, difficulty: Difficulty) -> bool {
+        self.scores.get(&(mode, difficulty)).is_some_and(|&(_, flawless)| flawless)
+    }
+
+    // Records a finished round and saves the file if it set a new best, returning whether it did.
+    pub fn record(&mut self, mode: GameMode, difficulty: Difficulty, score: u32, flawless: bool) -> bool {
+        if score <= self.get(mode, difficulty) {
+            return false;
+        }
+        self.scores.insert((mode, difficulty), (score, flawless));
+        if let Err(e) = self.save() {
+            eprintln!("Failed to save high scores: {}", e);
+        }
+        true
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        let mut entries: Vec<_> = self.scores.iter().collect();
+        entries.sort_by_key(|((mode, difficulty), _)| (mode.name(), difficulty.name()));
+        let contents: String = entries
+            .iter()
+            .map(|((mode, difficulty), (score, flawless))| {
+                let badge = if *flawless { " flawless" } else { "" };
+                format!("{} {} {}{}\n", mode.name(), difficulty.name(), score, badge)
+            })
+            .collect();
+        std::fs::write(&self.path, contents)
+    }
+}
@@ -0,0 +1,52 @@
+use macroquad::prelude::*;
+
+// Draw layers from back to front. Anything on a later layer is drawn over everything on earlier ones.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Layer {
+    // The backdrop and full-screen tints behind the action.
+    Background,
+    // Things lying under the action: barriers, spawn markers, aiming guides.
+    Ground,
+    // Enemies, the boss and their trails and effects.
+    Enemies,
+    Player,
+    // Player and enemy bullets, kept above ships so they are never hidden.
+    Projectiles,
+    // Full-screen flashes over the playfield.
+    Flash,
+    // Floating text and banners tied to events.
+    Overlay,
+    // Score, combo and other readouts, always on top.
+    Hud,
+}
+
+// Collects a frame's draw calls so they run in layer order rather than the order they were queued.
+pub struct DrawQueue<'a> {
+    commands: Vec<(Layer, Box<dyn FnOnce() + 'a>)>,
+}
+
+impl<'a> DrawQueue<'a> {
+    pub fn new() -> DrawQueue<'a> {
+        DrawQueue { commands: vec![] }
+    }
+
+    pub fn push(&mut self, layer: Layer, draw: impl FnOnce() + 'a) {
+        self.commands.push((layer, Box::new(draw)));
+    }
+
+    // Runs every queued draw, back layer first, keeping queue order within a layer.
+    // The playfield layers are drawn through the world camera and the HUD in plain screen space.
+    pub fn flush(mut self, world_camera: &Camera2D) {
+        self.commands.sort_by_key(|&(layer, _)| layer);
+        set_camera(world_camera);
+        let mut in_world = true;
+        for (layer, draw) in self.commands {
+            if in_world && layer == Layer::Hud {
+                set_default_camera();
+                in_world = false;
+            }
+            draw();
+        }
+        set_default_camera();
+    }
+}
@@ -1,9 +1,645 @@
-use macroquad::{audio::{load_sound, play_sound_once, Sound}, prelude::*};
+use macroquad::{audio::{load_sound, play_sound, PlaySoundParams, Sound}, prelude::*};
+use std::collections::HashMap;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+mod barrier;
+mod boss;
+mod enemy;
+mod formation;
+mod gravity_well;
+mod highscores;
+mod layers;
+mod milestone;
+mod music;
+mod popup;
+mod replay;
+mod powerup;
+mod practice;
+mod score;
+mod settings;
+mod shockwave;
+mod wave;
+mod weapon;
+
+use barrier::{block_bullets, draw_barriers, resolve_movement, update_barriers, Barrier};
+use boss::{draw_laser_beam, Boss};
+use enemy::{decoy_color, draw_enemy_effects, draw_enemy_trails, enemy_points, enemy_weights_problem, pick_enemy_kind, release_carried, update_builders, update_healers, update_lobbers, update_shockers, update_thieves, update_warpers, Enemy, EnemyKind};
+use formation::{draw_entry_arrow, numbered_formation, side_formation, sine_formation, Edge, KillOrder, KillOrderResult};
+use gravity_well::{draw_gravity_wells, gravity_pull, update_gravity_wells, GravityWell};
+use highscores::HighScores;
+use layers::{DrawQueue, Layer};
+use milestone::{reached_milestones, Milestone, MilestoneReward};
+use music::MusicTracks;
+use popup::{draw_popups, update_popups, ScorePopup};
+use replay::{ReplayBuffer, ReplayFrame};
+use powerup::{draw_powerup_shape, update_powerups, PowerUp, POWERUP_COLOR};
+use practice::DummyStats;
+use score::{format_score, ScoreStyle};
+use settings::{draw_mute_icon, draw_settings, load_settings, save_settings, toggle_mute, Setting};
+use shockwave::{draw_shockwaves, update_shockwaves, Shockwave};
+use wave::WaveState;
+use weapon::{AimAssist, Loadout};
+
+// Folder that game-over screenshots are written to.
+const SCREENSHOT_DIR: &str = "screenshots";
+
+// File that best scores are saved to.
+const HIGH_SCORE_FILE: &str = "highscores.txt";
+// File the options chosen in the pause menu are saved to.
+const SETTINGS_FILE: &str = "settings.txt";
+
+// Gap kept between the player and the bottom edge of the screen.
+const PLAYER_BOTTOM_GAP: f32 = 10.0;
+
+// Tunable gameplay settings shared by every round.
+struct GameConfig {
+    player_speed: f32,
+    // Shows a HUD meter of how close the nearest threat is.
+    show_danger_meter: bool,
+    // How gradually the ship picks up and sheds speed, from 0.0 (instant) to 1.0 (heavy).
+    movement_smoothing: f32,
+    // Where the player starts each round, within the player's movement range (0.0 to 1.0 on each axis).
+    player_spawn: Vec2,
+    // Region the player may move in, as a fraction of the movement range (0.0 to 1.0 on each axis).
+    player_bounds: Rect,
+    // Distance in pixels the playfield is inset from each screen edge, for displays that crop the picture.
+    margin_top: f32,
+    margin_bottom: f32,
+    margin_left: f32,
+    margin_right: f32,
+    // Seconds after a kill before the combo breaks.
+    combo_window: f32,
+    // Kills needed in a combo to raise the score multiplier by one.
+    combo_step: u32,
+    max_combo_multiplier: u32,
+    // How much each kill in the current combo shortens the weapon cooldown, as a fraction of it.
+    combo_fire_rate_step: f32,
+    // The most a combo can shorten the weapon cooldown, as a fraction of it.
+    max_combo_fire_rate_bonus: f32,
+    // How fast the combo window runs down while a boss is alive, relative to normal; 0 pauses it.
+    boss_combo_decay_rate: f32,
+    // Turns off purely cosmetic effects such as the combo tint.
+    reduce_effects: bool,
+    // Volume of sound effects, from 0.0 (silent) to 1.0.
+    sfx_volume: f32,
+    music_volume: f32,
+    // Speed the background scrolls at, in pixels per second, and the direction it moves in.
+    background_scroll_speed: f32,
+    background_scroll_direction: Vec2,
+    // Speeds the scroll up along with the difficulty ramp's enemy speed multiplier.
+    background_scroll_ramps: bool,
+    // Scale of the playfield view: above 1 zooms in around the player, below 1 shows past the edges.
+    camera_zoom: f32,
+    // Highest frame rate to run at, or 0 for no cap.
+    frame_cap: u32,
+    // The sound effect and music volumes from before the mute-all hotkey was pressed, while it is on.
+    // Kept for the whole session but never saved, so a restart always comes back with sound.
+    muted_levels: Option<(f32, f32)>,
+    // Seconds taken to cross-fade between the normal and boss tracks.
+    music_fade_time: f32,
+    // Key that switches to the next owned weapon.
+    cycle_weapon_key: KeyCode,
+    // Draws a faint guide along the path the current weapon's shots will take.
+    show_firing_line: bool,
+    // Floats the damage of each hit above the enemy that took it.
+    show_damage_numbers: bool,
+    // Bends straight shots toward nearby enemies. A round played with it on never sets a high score.
+    aim_assist: AimAssist,
+    // Seconds an incoming enemy is telegraphed before it appears.
+    telegraph_time: f32,
+    // How the score is written on the HUD and results screen.
+    score_style: ScoreStyle,
+    enemy_speed: f32,
+    // Relative chance of each enemy kind spawning, per mode. Weights are normalized by their sum,
+    // kinds left out never spawn, and a mode without a usable table only spawns basic enemies.
+    enemy_weights: HashMap<GameMode, Vec<(EnemyKind, f32)>>,
+    fast_speed_multiplier: f32,
+    // Enemies moving faster than this (in pixels per second) leave a motion trail.
+    trail_speed_threshold: f32,
+    // Acceleration of a rammer level with the player, in pixels per second squared.
+    rammer_acceleration: f32,
+    // Fastest a rammer's base speed can climb to, before the difficulty and time multiplier is applied.
+    rammer_max_speed: f32,
+    lobber_fire_interval: f32,
+    // Upward speed a lobbed shot is launched with, in pixels per second.
+    lobber_launch_speed: f32,
+    // Downward acceleration on lobbed shots, in pixels per second squared.
+    lob_gravity: f32,
+    // Depth a shocker stops at to charge, as a fraction of the screen height, and how long it charges.
+    shockwave_trigger_depth: f32,
+    shockwave_charge_time: f32,
+    // Depth a warper opens its gravity well at, as a fraction of the screen height.
+    gravity_well_trigger_depth: f32,
+    // Seconds a gravity well lasts and the distance it reaches, in pixels.
+    gravity_well_duration: f32,
+    gravity_well_radius: f32,
+    // Speed a well drags the player at from its center, in pixels per second. It is kept well under the
+    // player's own speed so the ship can always fly clear.
+    gravity_well_strength: f32,
+    // Acceleration a well gives bullets at its center, in pixels per second squared.
+    gravity_well_bullet_pull: f32,
+    // Speed a shockwave ring expands at, in pixels per second.
+    shockwave_speed: f32,
+    // Width of the safe gap in a shockwave ring, in radians.
+    shockwave_gap: f32,
+    // How far a decoy's glow is shifted from a real pickup's toward red, from 0 (identical) to 1 (obvious).
+    decoy_tell: f32,
+    // Chance a destroyed enemy drops a bomb pickup.
+    powerup_drop_chance: f32,
+    powerup_fall_speed: f32,
+    // How fast a thief flies toward a pickup and away with it.
+    thief_speed: f32,
+    builder_health: u32,
+    // Seconds between barriers dropped by a builder, including the first.
+    builder_drop_interval: f32,
+    // Hits a barrier takes before breaking.
+    barrier_health: u32,
+    // Seconds a barrier stands before crumbling on its own.
+    barrier_lifetime: f32,
+    barrier_size: Vec2,
+    // Seconds between sweeping formations.
+    formation_interval: f32,
+    formation_size: usize,
+    // Horizontal gap between neighbouring enemies in a formation.
+    formation_spacing: f32,
+    // How far a formation sways either side of its starting line.
+    formation_amplitude: f32,
+    // Seconds for one full sway of a formation.
+    formation_period: f32,
+    // Phase offset between neighbouring enemies, in radians, so the line ripples.
+    formation_index_phase: f32,
+    // Chance that a formation sweeps in from a side instead of from the top.
+    formation_side_chance: f32,
+    // Horizontal speed of a side formation, in pixels per second.
+    formation_side_speed: f32,
+    // Height a side formation enters at, as a fraction of the screen height.
+    formation_side_height: f32,
+    // Fraction of the normal enemy speed a side formation descends at.
+    formation_side_descent: f32,
+    // Chance that a formation is numbered and has to be destroyed in order.
+    ordered_formation_chance: f32,
+    // Points for destroying a numbered formation in order, and lost for breaking the order.
+    ordered_formation_bonus: u32,
+    ordered_formation_penalty: u32,
+    // Shows where telegraphed enemies are about to appear, including arrows for side formations.
+    show_spawn_indicators: bool,
+    healer_health: u32,
+    // Distance from a healer's center within which enemies are healed.
+    healer_radius: f32,
+    // Seconds between healing pulses.
+    healer_interval: f32,
+    healer_amount: u32,
+    // Shape of the difficulty ramp over the course of a round.
+    difficulty_curve: DifficultyCurve,
+    // Seconds until the ramp reaches its hardest settings.
+    difficulty_ramp_time: f32,
+    // Length of each plateau on the stepped curve.
+    difficulty_step_time: f32,
+    base_spawn_interval: f32,
+    // Seconds at the start of a round before the first enemy spawns.
+    spawn_grace_time: f32,
+    min_spawn_interval: f32,
+    max_speed_multiplier: f32,
+    // Seconds of regular play before each boss arrives.
+    boss_interval: f32,
+    boss_health: u32,
+    boss_speed: f32,
+    boss_fire_interval: f32,
+    boss_bullet_speed: f32,
+    boss_points: u32,
+    // Seconds into a boss fight before the boss enrages.
+    boss_enrage_time: f32,
+    boss_enrage_speed_multiplier: f32,
+    boss_enrage_fire_multiplier: f32,
+    // Health fractions at which the boss moves into its next phase, highest first.
+    boss_phase_thresholds: Vec<f32>,
+    // Extra speed and fire rate gained per boss phase, as a fraction of the base.
+    boss_phase_speed_step: f32,
+    // Seconds between the boss's laser sweeps, counted while no sweep is running.
+    boss_laser_interval: f32,
+    // Seconds the warning line shows before the laser beam switches on.
+    boss_laser_telegraph: f32,
+    // How fast the laser beam turns, in radians per second.
+    boss_laser_sweep_speed: f32,
+    // Total angle the laser beam sweeps through, in radians, centered on straight down.
+    boss_laser_sweep_arc: f32,
+    // Enemies in the first wave of wave mode, and how many more each later wave adds.
+    wave_base_size: u32,
+    wave_size_step: u32,
+    // Seconds of calm between waves.
+    wave_break_time: f32,
+    // Seconds allowed per enemy, counted from the wave's last spawn, before a wave clear earns no speed bonus.
+    wave_par_time_per_enemy: f32,
+    // Speed bonus for an instant wave clear.
+    wave_bonus_max: u32,
+    // Shape of the speed bonus curve: above 1.0 rewards only very quick clears, below 1.0 is more forgiving.
+    wave_bonus_exponent: f32,
+    // Seconds of hits averaged into the practice dummy's damage per second.
+    dummy_dps_window: f32,
+    // Most enemy bullets allowed on screen at once; further shots are not fired.
+    max_enemy_bullets: usize,
+    // Weights of the kill value formula: a base value plus bonuses for extra health, extra speed and special abilities.
+    points_base: f32,
+    points_per_health: f32,
+    // Points per multiple of the base enemy speed above it.
+    points_per_speed: f32,
+    points_ability_bonus: f32,
+    // Seconds of play before a death that are kept for the slow-motion replay.
+    death_replay_time: f32,
+    // Playback speed of the death replay, as a fraction of real time.
+    death_replay_speed: f32,
+    // Lives the player starts each round with, and seconds of invulnerability after losing one.
+    player_lives: u32,
+    invulnerable_time: f32,
+    // Points added for each score milestone reached without losing a life.
+    flawless_milestone_bonus: u32,
+    // Modes where losing the last life with a full second wind charge revives the player once; none by default.
+    second_wind_modes: Vec<GameMode>,
+    // Points that must be scored at or above the minimum combo multiplier to fill the second wind charge.
+    second_wind_charge: u32,
+    second_wind_min_multiplier: u32,
+    // Bombs the player starts each round with.
+    starting_bombs: u32,
+    // Score thresholds in classic mode and their rewards, in ascending order.
+    milestones: Vec<Milestone>,
+    // What letting an enemy through costs, per mode; modes left out let them go for free.
+    escape_rules: HashMap<GameMode, EscapeRule>,
+    // Points each escaped enemy takes off the score under the score penalty rule.
+    escape_penalty: u32,
+}
+
+// How quickly the difficulty climbs from its starting values to its hardest.
+#[derive(Clone, Copy, PartialEq)]
+enum DifficultyCurve {
+    Linear,
+    // Holds steady for a while, then jumps up.
+    Stepped,
+    // Starts gently and accelerates toward the end of the ramp.
+    Exponential,
+}
+
+impl DifficultyCurve {
+    const ALL: [DifficultyCurve; 3] = [DifficultyCurve::Linear, DifficultyCurve::Stepped, DifficultyCurve::Exponential];
+
+    fn name(self) -> &'static str {
+        match self {
+            DifficultyCurve::Linear => "Linear",
+            DifficultyCurve::Stepped => "Stepped",
+            DifficultyCurve::Exponential => "Exponential",
+        }
+    }
+}
+
+// What happens when an enemy gets past the player and off the bottom of the screen.
+#[derive(Clone, Copy, PartialEq)]
+enum EscapeRule {
+    // The enemy simply disappears.
+    Free,
+    // Each escaped enemy takes the configured penalty off the score.
+    ScorePenalty,
+    // Any escape costs a life, just like being hit.
+    LoseLife,
+}
+
+impl EscapeRule {
+    const ALL: [EscapeRule; 3] = [EscapeRule::Free, EscapeRule::ScorePenalty, EscapeRule::LoseLife];
+
+    fn name(self) -> &'static str {
+        match self {
+            EscapeRule::Free => "Free",
+            EscapeRule::ScorePenalty => "Points",
+            EscapeRule::LoseLife => "Life",
+        }
+    }
+}
+
+// The spawn interval and enemy speed multiplier in effect at a point in the round.
+struct Pacing {
+    spawn_interval: f32,
+    speed_multiplier: f32,
+}
+
+// How hard a round is, chosen by the player before it starts.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Difficulty> {
+        Difficulty::ALL.into_iter().find(|d| d.name() == name)
+    }
+
+    // Returns the difficulty after this one, wrapping around (or before it if `step` is negative).
+    fn cycle(self, step: i32) -> Difficulty {
+        let index = Difficulty::ALL.iter().position(|&d| d == self).unwrap() as i32;
+        Difficulty::ALL[(index + step).rem_euclid(Difficulty::ALL.len() as i32) as usize]
+    }
+
+    // Returns how much this difficulty stretches the spawn interval and scales enemy speed.
+    fn scales(self) -> (f32, f32) {
+        match self {
+            Difficulty::Easy => (1.3, 0.8),
+            Difficulty::Normal => (1.0, 1.0),
+            Difficulty::Hard => (0.75, 1.25),
+        }
+    }
+}
+
+// The rule set a round is played under; high scores are kept separately for each.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum GameMode {
+    // Endless play with formations and recurring bosses.
+    Classic,
+    // Numbered waves of a fixed size, with a bonus for clearing each one quickly.
+    Waves,
+    // No enemies spawn on their own; a damage dummy can be placed for testing weapons.
+    Practice,
+}
+
+impl GameMode {
+    const ALL: [GameMode; 3] = [GameMode::Classic, GameMode::Waves, GameMode::Practice];
+
+    fn name(self) -> &'static str {
+        match self {
+            GameMode::Classic => "Classic",
+            GameMode::Waves => "Waves",
+            GameMode::Practice => "Practice",
+        }
+    }
+
+    // Returns whether rounds in this mode count toward the high scores.
+    fn records_scores(self) -> bool {
+        self != GameMode::Practice
+    }
+
+    fn from_name(name: &str) -> Option<GameMode> {
+        GameMode::ALL.into_iter().find(|m| m.name() == name)
+    }
+
+    // Returns the mode after this one, wrapping around (or before it if `step` is negative).
+    fn cycle(self, step: i32) -> GameMode {
+        let index = GameMode::ALL.iter().position(|&m| m == self).unwrap() as i32;
+        GameMode::ALL[(index + step).rem_euclid(GameMode::ALL.len() as i32) as usize]
+    }
+}
+
+impl GameConfig {
+    // Pinned to the bottom of the screen, moving only sideways.
+    const BOTTOM_LOCKED: Rect = Rect { x: 0.0, y: 1.0, w: 1.0, h: 0.0 };
+    // Free movement within the lower third of the screen.
+    const BOTTOM_THIRD: Rect = Rect { x: 0.0, y: 2.0 / 3.0, w: 1.0, h: 1.0 / 3.0 };
+    // Free movement across the whole screen.
+    const FULL_SCREEN: Rect = Rect { x: 0.0, y: 0.0, w: 1.0, h: 1.0 };
+
+    // Returns the area of the screen inside the margins, where the action takes place.
+    fn playfield(&self) -> Rect {
+        Rect::new(
+            self.margin_left,
+            self.margin_top,
+            (screen_width() - self.margin_left - self.margin_right).max(0.0),
+            (screen_height() - self.margin_top - self.margin_bottom).max(0.0),
+        )
+    }
+
+    fn has_margins(&self) -> bool {
+        self.margin_top > 0.0 || self.margin_bottom > 0.0 || self.margin_left > 0.0 || self.margin_right > 0.0
+    }
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            player_speed: 700.0,
+            player_spawn: vec2(0.5, 1.0),
+            player_bounds: GameConfig::BOTTOM_LOCKED,
+            margin_top: 0.0,
+            margin_bottom: 0.0,
+            margin_left: 0.0,
+            margin_right: 0.0,
+            combo_window: 2.0,
+            combo_fire_rate_step: 0.01,
+            max_combo_fire_rate_bonus: 0.3,
+            combo_step: 5,
+            max_combo_multiplier: 4,
+            boss_combo_decay_rate: 0.25,
+            reduce_effects: false,
+            movement_smoothing: 0.0,
+            show_danger_meter: false,
+            sfx_volume: 1.0,
+            music_volume: 0.5,
+            background_scroll_speed: 40.0,
+            background_scroll_direction: vec2(0.0, 1.0),
+            background_scroll_ramps: true,
+            camera_zoom: 1.0,
+            frame_cap: 0,
+            muted_levels: None,
+            music_fade_time: 1.0,
+            cycle_weapon_key: KeyCode::Q,
+            show_firing_line: false,
+            show_damage_numbers: false,
+            aim_assist: AimAssist::Off,
+            telegraph_time: 0.6,
+            score_style: ScoreStyle::Separated,
+            enemy_speed: 400.0,
+            enemy_weights: HashMap::from([
+                (GameMode::Classic, vec![
+                    (EnemyKind::Basic, 0.30),
+                    (EnemyKind::Fast, 0.20),
+                    (EnemyKind::Healer, 0.15),
+                    (EnemyKind::Rammer, 0.10),
+                    (EnemyKind::Builder, 0.08),
+                    (EnemyKind::Lobber, 0.08),
+                    (EnemyKind::Shocker, 0.05),
+                    (EnemyKind::Decoy, 0.04),
+                    (EnemyKind::Thief, 0.04),
+                    (EnemyKind::Warper, 0.04),
+                ]),
+                // Waves lean toward swarms of quick, fragile enemies.
+                (GameMode::Waves, vec![
+                    (EnemyKind::Basic, 0.40),
+                    (EnemyKind::Fast, 0.35),
+                    (EnemyKind::Rammer, 0.10),
+                    (EnemyKind::Healer, 0.05),
+                    (EnemyKind::Lobber, 0.05),
+                    (EnemyKind::Shocker, 0.05),
+                ]),
+            ]),
+            fast_speed_multiplier: 1.6,
+            trail_speed_threshold: 600.0,
+            rammer_acceleration: 900.0,
+            rammer_max_speed: 900.0,
+            lobber_fire_interval: 1.5,
+            lobber_launch_speed: 300.0,
+            lob_gravity: 600.0,
+            shockwave_trigger_depth: 0.35,
+            shockwave_charge_time: 0.8,
+            shockwave_speed: 260.0,
+            shockwave_gap: 0.8,
+            gravity_well_trigger_depth: 0.3,
+            gravity_well_duration: 3.0,
+            gravity_well_radius: 220.0,
+            gravity_well_strength: 300.0,
+            gravity_well_bullet_pull: 1500.0,
+            decoy_tell: 0.3,
+            powerup_drop_chance: 0.08,
+            powerup_fall_speed: 150.0,
+            thief_speed: 220.0,
+            builder_health: 2,
+            builder_drop_interval: 1.2,
+            barrier_health: 6,
+            barrier_lifetime: 6.0,
+            barrier_size: vec2(120.0, 18.0),
+            formation_interval: 20.0,
+            formation_size: 6,
+            formation_spacing: 80.0,
+            formation_amplitude: 120.0,
+            formation_period: 2.5,
+            formation_index_phase: 0.5,
+            formation_side_chance: 0.5,
+            formation_side_speed: 300.0,
+            formation_side_height: 0.15,
+            formation_side_descent: 0.25,
+            ordered_formation_chance: 0.25,
+            ordered_formation_bonus: 40,
+            ordered_formation_penalty: 10,
+            show_spawn_indicators: true,
+            healer_health: 3,
+            healer_radius: 160.0,
+            healer_interval: 2.0,
+            healer_amount: 1,
+            difficulty_curve: DifficultyCurve::Linear,
+            difficulty_ramp_time: 120.0,
+            difficulty_step_time: 30.0,
+            base_spawn_interval: 1.5,
+            spawn_grace_time: 2.0,
+            min_spawn_interval: 0.4,
+            max_speed_multiplier: 2.0,
+            boss_interval: 60.0,
+            boss_health: 40,
+            boss_speed: 150.0,
+            boss_fire_interval: 1.0,
+            boss_bullet_speed: 350.0,
+            boss_points: 25,
+            boss_enrage_time: 30.0,
+            boss_enrage_speed_multiplier: 1.8,
+            boss_enrage_fire_multiplier: 2.0,
+            boss_phase_thresholds: vec![0.66, 0.33],
+            boss_phase_speed_step: 0.3,
+            boss_laser_interval: 9.0,
+            boss_laser_telegraph: 1.2,
+            boss_laser_sweep_speed: 0.6,
+            boss_laser_sweep_arc: 2.0,
+            wave_base_size: 8,
+            wave_size_step: 3,
+            wave_break_time: 2.5,
+            wave_par_time_per_enemy: 2.0,
+            wave_bonus_max: 50,
+            wave_bonus_exponent: 1.5,
+            dummy_dps_window: 3.0,
+            max_enemy_bullets: 120,
+            points_base: 1.0,
+            points_per_health: 1.0,
+            points_per_speed: 1.0,
+            points_ability_bonus: 1.0,
+            death_replay_time: 1.0,
+            death_replay_speed: 0.25,
+            player_lives: 3,
+            invulnerable_time: 1.5,
+            flawless_milestone_bonus: 25,
+            second_wind_modes: vec![],
+            second_wind_charge: 150,
+            second_wind_min_multiplier: 2,
+            starting_bombs: 1,
+            milestones: vec![
+                Milestone { score: 50, reward: MilestoneReward::Bomb },
+                Milestone { score: 100, reward: MilestoneReward::WeaponUpgrade },
+                Milestone { score: 200, reward: MilestoneReward::Bomb },
+                Milestone { score: 350, reward: MilestoneReward::WeaponUpgrade },
+                Milestone { score: 500, reward: MilestoneReward::Bomb },
+            ],
+            escape_rules: HashMap::from([(GameMode::Classic, EscapeRule::Free), (GameMode::Waves, EscapeRule::Free)]),
+            escape_penalty: 5,
+        }
+    }
+}
+
+// Radius of the round shots fired by enemies.
+const ENEMY_BULLET_RADIUS: f32 = 6.0;
+
+// Textures and sounds loaded once at startup and shared by every round.
+struct Assets {
+    player_texture: Texture2D,
+    enemy_texture: Texture2D,
+    boss_texture: Texture2D,
+    background_texture: Texture2D,
+    shoot_sound: Sound,
+    explosion_sound: Sound,
+    game_over_sound: Sound,
+    enrage_sound: Sound,
+    // Optional, so a missing file only silences the warning.
+    spawn_warning_sound: Option<Sound>,
+    music: MusicTracks,
+}
+
+impl Assets {
+    async fn load() -> Assets {
+        Assets {
+            player_texture: load_texture("assets/player.png").await.unwrap(),
+            enemy_texture: load_texture("assets/enemy.png").await.unwrap(),
+            boss_texture: load_texture("assets/Faction7-Spaceships-by-MillionthVector/blue2.png").await.unwrap(),
+            background_texture: load_texture("assets/background_2.png").await.unwrap(),
+            shoot_sound: load_sound("assets/shoot.wav").await.unwrap(),
+            explosion_sound: load_sound("assets/short_explode.wav").await.unwrap(),
+            game_over_sound: load_sound("assets/game_over.wav").await.unwrap(),
+            enrage_sound: load_sound("assets/explosion.wav").await.unwrap(),
+            spawn_warning_sound: load_sound("assets/spawn_warning.wav").await.ok(),
+            music: MusicTracks::load().await,
+        }
+    }
+}
 
 // Defines the data for a game object.
 struct Player { pos: Vec2, size: Vec2 }
-struct Bullet { pos: Vec2 }
-struct Enemy { pos: Vec2 }
+struct Bullet { pos: Vec2, vel: Vec2 }
+// Gravity is the downward acceleration that bends lobbed shots into an arc, 0 for straight shots.
+struct EnemyBullet { pos: Vec2, vel: Vec2, gravity: f32 }
+// A player bullet striking an enemy, reported back from collision handling.
+// Pos is the top center of the enemy at the moment it was hit, and carrying marks a thief holding a pickup.
+struct EnemyHit { kind: EnemyKind, pos: Vec2, damage: u32, killed: bool, points: u32, sequence: Option<usize>, carrying: bool }
+// Holds back an enemy while a marker warns where it is about to appear.
+struct SpawnTelegraph { enemy: Enemy, timer: f32 }
+
+// How quickly the HUD score catches up with the real score; higher is snappier.
+const SCORE_COUNT_RATE: f32 = 10.0;
+
+// Fraction of the player's speed a gravity well's drag is capped at, so it can never pin the ship in place.
+const GRAVITY_WELL_MAX_DRAG: f32 = 0.6;
+
+// Distance from the player's center at which a threat starts to register on the danger meter.
+const DANGER_RANGE: f32 = 250.0;
+
+// Volume of the spawn warning relative to other sound effects.
+const SPAWN_WARNING_VOLUME: f32 = 0.4;
+// Color of the floating numbers showing damage dealt, kept apart from score popups.
+const DAMAGE_NUMBER_COLOR: Color = Color::new(1.0, 0.45, 0.45, 1.0);
+
+// Plays a sound effect once at the given volume.
+fn play_sfx(sound: &Sound, volume: f32) {
+    play_sound(sound, PlaySoundParams { looped: false, volume });
+}
 
 // Returns a smaller, centered collision box for an object.
 fn get_hitbox(pos: Vec2, size: Vec2, inset: f32) -> Rect {
@@ -15,119 +651,1006 @@ fn get_hitbox(pos: Vec2, size: Vec2, inset: f32) -> Rect {
     )
 }
 
-// Checks for bullet-enemy collisions, removes hit objects, and returns the number of enemies killed.
+// Maps a point in the player's movement range (0.0 to 1.0 on each axis) to a position within the playfield.
+fn player_range_to_screen(point: Vec2, size: Vec2, field: Rect) -> Vec2 {
+    vec2(
+        field.x + point.x * (field.w - size.x),
+        field.y + point.y * (field.h - size.y - PLAYER_BOTTOM_GAP),
+    )
+}
+
+// Returns how close the nearest enemy, enemy bullet or boss is to `center`, or None with no threats around.
+fn nearest_threat_distance(center: Vec2, enemies: &[Enemy], enemy_bullets: &[EnemyBullet], boss: Option<&Boss>, enemy_size: Vec2) -> Option<f32> {
+    let enemy_centers = enemies.iter().filter(|e| e.kind != EnemyKind::Dummy).map(|e| e.hitbox(enemy_size).center());
+    let bullet_centers = enemy_bullets.iter().map(|b| b.pos);
+    let boss_center = boss.map(|b| b.hitbox().center());
+    enemy_centers.chain(bullet_centers).chain(boss_center).map(|p| p.distance(center)).min_by(f32::total_cmp)
+}
+
+// Eases the ship's velocity toward the one the held keys ask for, or snaps to it with no smoothing.
+// Less smoothing means a faster response, so even small amounts stay close to instant.
+fn smooth_velocity(velocity: Vec2, target: Vec2, smoothing: f32, dt: f32) -> Vec2 {
+    if smoothing <= 0.0 {
+        return target;
+    }
+    let rate = 4.0 + 36.0 * (1.0 - smoothing.min(1.0));
+    velocity + (target - velocity) * (1.0 - (-rate * dt).exp())
+}
+
+// Keeps a player position inside the configured movement bounds of the playfield.
+fn clamp_to_bounds(pos: Vec2, size: Vec2, bounds: Rect, field: Rect) -> Vec2 {
+    let min = player_range_to_screen(bounds.point(), size, field);
+    let max = player_range_to_screen(bounds.point() + bounds.size(), size, field);
+    pos.clamp(min, max)
+}
+
+// Returns the pacing after the given number of seconds, following the configured curve.
+fn pacing_at(elapsed: f32, config: &GameConfig, difficulty: Difficulty) -> Pacing {
+    let ramp = config.difficulty_ramp_time;
+    let progress = match config.difficulty_curve {
+        DifficultyCurve::Linear => elapsed / ramp,
+        DifficultyCurve::Stepped => {
+            let step = config.difficulty_step_time;
+            (elapsed / step).floor() * step / ramp
+        }
+        DifficultyCurve::Exponential => {
+            let k = 3.0;
+            ((k * elapsed / ramp).exp() - 1.0) / (k.exp() - 1.0)
+        }
+    }
+    .clamp(0.0, 1.0);
+    let (spawn_scale, speed_scale) = difficulty.scales();
+    Pacing {
+        spawn_interval: (config.base_spawn_interval + (config.min_spawn_interval - config.base_spawn_interval) * progress) * spawn_scale,
+        speed_multiplier: (1.0 + (config.max_speed_multiplier - 1.0) * progress) * speed_scale,
+    }
+}
+
+// Returns the score multiplier earned by a combo of the given length.
+fn combo_multiplier(combo: u32, config: &GameConfig) -> u32 {
+    (1 + combo / config.combo_step).min(config.max_combo_multiplier)
+}
+
+// Returns the fraction the current combo takes off the weapon cooldown, up to the configured cap.
+fn combo_fire_rate_bonus(combo: u32, config: &GameConfig) -> f32 {
+    (combo as f32 * config.combo_fire_rate_step).clamp(0.0, config.max_combo_fire_rate_bonus)
+}
+
+// Returns a warm overlay that deepens as the combo multiplier climbs, or none without a combo.
+fn combo_tint(multiplier: u32) -> Option<Color> {
+    if multiplier <= 1 {
+        return None;
+    }
+    let intensity = (multiplier - 1) as f32 * 0.08;
+    Some(Color::new(1.0, 0.45, 0.1, intensity.min(0.3)))
+}
+
+// Checks for bullet-enemy collisions, removes hit objects, and returns every hit that landed.
 fn handle_collisions(
     bullets: &mut Vec<Bullet>,
-    enemies: &mut Vec<Enemy>,
+    enemies: &mut [Enemy],
     bullet_size: Vec2,
     enemy_size: Vec2,
     explosion_sound: &Sound,
-) -> u32 {
-    let mut enemies_killed = 0;
+    config: &GameConfig,
+) -> Vec<EnemyHit> {
+    let mut enemy_hits = vec![];
     bullets.retain(|bullet| {
         let bullet_rect = Rect::new(bullet.pos.x, bullet.pos.y, bullet_size.x, bullet_size.y);
         let mut hit_an_enemy = false;
         for enemy in enemies.iter_mut() {
-            let enemy_hitbox = get_hitbox(enemy.pos, enemy_size, 8.0);
+            let enemy_hitbox = enemy.hitbox(enemy_size);
             if bullet_rect.overlaps(&enemy_hitbox) {
-                play_sound_once(explosion_sound);
-                enemy.pos.y = screen_height() + 100.0; // Mark enemy for deletion.
                 hit_an_enemy = true;
-                enemies_killed += 1;
+                let damage = 1;
+                if enemy.kind != EnemyKind::Dummy {
+                    enemy.health = enemy.health.saturating_sub(damage);
+                }
+                let killed = enemy.health == 0;
+                let pos = enemy.pos + vec2(enemy_size.x / 2.0, 0.0);
+                if killed {
+                    play_sfx(explosion_sound, config.sfx_volume);
+                    enemy.pos.y = screen_height() + 100.0; // Mark enemy for deletion.
+                }
+                let points = enemy_points(enemy.kind, enemy.max_health, enemy.kind.base_speed(config), config);
+                enemy_hits.push(EnemyHit { kind: enemy.kind, pos, damage, killed, points, sequence: enemy.sequence, carrying: enemy.carrying });
                 break;
             }
         }
         !hit_an_enemy // Remove bullet if it hit something.
     });
-    enemies_killed
+    enemy_hits
 }
 
-// Runs a single round of the game and returns the final score.
-async fn play_game(
-    player_texture: &Texture2D,
-    enemy_texture: &Texture2D,
-    shoot_sound: &Sound,
-    explosion_sound: &Sound,
-    game_over_sound: &Sound,
-    background_texture: &Texture2D,
-) -> u32 {
+// Drops a numbered formation's kill order along with the numbers shown on its remaining enemies.
+fn abandon_kill_order(kill_order: &mut Option<KillOrder>, enemies: &mut [Enemy]) {
+    for enemy in enemies.iter_mut() { enemy.sequence = None; }
+    *kill_order = None;
+}
+
+// Applies player bullets that strike the boss and returns the number of hits.
+fn handle_boss_hits(bullets: &mut Vec<Bullet>, boss: &mut Boss, bullet_size: Vec2) -> u32 {
+    let boss_hitbox = boss.hitbox();
+    let mut hits = 0;
+    bullets.retain(|bullet| {
+        let bullet_rect = Rect::new(bullet.pos.x, bullet.pos.y, bullet_size.x, bullet_size.y);
+        let hit = boss.health > 0 && bullet_rect.overlaps(&boss_hitbox);
+        if hit {
+            boss.health -= 1;
+            hits += 1;
+        }
+        !hit
+    });
+    hits
+}
+
+// Adds an enemy bullet unless the cap is already reached, returning whether it was fired.
+fn spawn_enemy_bullet(enemy_bullets: &mut Vec<EnemyBullet>, bullet: EnemyBullet, cap: usize) -> bool {
+    if enemy_bullets.len() >= cap {
+        return false;
+    }
+    enemy_bullets.push(bullet);
+    true
+}
+
+// Returns the collision box of an enemy bullet.
+fn enemy_bullet_rect(bullet: &EnemyBullet) -> Rect {
+    Rect::new(
+        bullet.pos.x - ENEMY_BULLET_RADIUS,
+        bullet.pos.y - ENEMY_BULLET_RADIUS,
+        ENEMY_BULLET_RADIUS * 2.0,
+        ENEMY_BULLET_RADIUS * 2.0,
+    )
+}
+
+// Returns the draw color of an enemy bullet, marking lobbed shots apart from straight ones.
+fn enemy_bullet_color(bullet: &EnemyBullet) -> Color {
+    if bullet.gravity > 0.0 { ORANGE } else { MAGENTA }
+}
+
+// Plays back the recorded final moments of a round in slow motion, lingering on the impact.
+// Any key skips straight to the results screen.
+async fn play_death_replay(replay: &ReplayBuffer, assets: &Assets, config: &GameConfig, player_size: Vec2, enemy_size: Vec2, bullet_size: Vec2) {
+    let Some((start, end)) = replay.span() else { return };
+    let mut time = start;
+    let mut linger = 0.6;
+    while linger > 0.0 {
+        // Let the frame with the skipping key press end here, so the results screen doesn't act on it too.
+        if get_last_key_pressed().is_some() {
+            next_frame().await;
+            return;
+        }
+        let dt = get_frame_time();
+        if time < end { time = (time + dt * config.death_replay_speed).min(end); } else { linger -= dt; }
+        let Some(frame) = replay.frame_at(time) else { return };
+
+        let mut draws = DrawQueue::new();
+        draws.push(Layer::Background, || {
+            draw_texture_ex(&assets.background_texture, 0.0, 0.0, Color::new(0.6, 0.6, 0.7, 1.0), DrawTextureParams {
+                dest_size: Some(vec2(screen_width(), screen_height())),
+                ..Default::default()
+            });
+        });
+        draws.push(Layer::Ground, || draw_gravity_wells(&frame.gravity_wells, config));
+        draws.push(Layer::Enemies, || {
+            for &(pos, kind, tint) in &frame.enemies {
+                if kind == EnemyKind::Decoy {
+                    draw_powerup_shape(pos + enemy_size / 2.0, decoy_color(config));
+                } else {
+                    draw_texture_ex(&assets.enemy_texture, pos.x, pos.y, tint, DrawTextureParams { dest_size: Some(enemy_size), ..Default::default() });
+                }
+            }
+            if let Some(rect) = frame.boss {
+                draw_texture_ex(&assets.boss_texture, rect.x, rect.y, WHITE, DrawTextureParams { dest_size: Some(rect.size()), ..Default::default() });
+            }
+        });
+        draws.push(Layer::Player, || {
+            draw_texture_ex(&assets.player_texture, frame.player.x, frame.player.y, WHITE, DrawTextureParams { dest_size: Some(player_size), ..Default::default() });
+        });
+        draws.push(Layer::Projectiles, || {
+            for pos in &frame.bullets { draw_rectangle(pos.x, pos.y, bullet_size.x, bullet_size.y, RED); }
+            for &(pos, color) in &frame.enemy_bullets { draw_circle(pos.x, pos.y, ENEMY_BULLET_RADIUS, color); }
+            draw_shockwaves(&frame.shockwaves, config);
+            if let Some((start, end, telegraphed)) = frame.boss_laser {
+                draw_laser_beam(start, end, telegraphed);
+            }
+        });
+        draws.push(Layer::Overlay, || {
+            if time >= end {
+                let center = frame.player + player_size / 2.0;
+                draw_circle_lines(center.x, center.y, player_size.x * 0.75, 3.0, RED);
+            }
+        });
+        draws.push(Layer::Hud, || {
+            draw_text("REPLAY", 20.0, 30.0, 30.0, WHITE);
+            draw_text("Press any key to skip", 20.0, screen_height() - 20.0, 20.0, LIGHTGRAY);
+        });
+        draws.flush(&world_camera(config.camera_zoom, frame.player + player_size / 2.0));
+        next_frame().await
+    }
+}
+
+// Draws the background tiled so it wraps around at `offset`, which should lie within one screen size.
+fn draw_scrolling_background(texture: &Texture2D, offset: Vec2, tint: Color) {
+    let size = vec2(screen_width(), screen_height());
+    for tile in [vec2(0.0, 0.0), vec2(-1.0, 0.0), vec2(0.0, -1.0), vec2(-1.0, -1.0)] {
+        let pos = offset + tile * size;
+        draw_texture_ex(texture, pos.x, pos.y, tint, DrawTextureParams { dest_size: Some(size), ..Default::default() });
+    }
+}
+
+// Returns the camera the playfield is drawn through at the given zoom.
+// Zoomed in, the view follows `focus` without leaving the playfield; zoomed out, the playfield is centered with room around it.
+// Only drawing goes through the camera, so movement and collisions stay in playfield coordinates.
+fn world_camera(zoom: f32, focus: Vec2) -> Camera2D {
+    let world = vec2(screen_width(), screen_height());
+    let view = world / zoom;
+    let half = view / 2.0;
+    let center = if zoom > 1.0 { focus.clamp(half, world - half) } else { world / 2.0 };
+    // Drawing to the screen flips the camera's y axis, so a negative height keeps y growing downward like the default camera.
+    Camera2D::from_display_rect(Rect::new(center.x - half.x, center.y + half.y, view.x, -view.y))
+}
+
+// Paces frames so the game never runs faster than the configured frame cap.
+struct FrameLimiter { last_frame: Instant }
+
+impl FrameLimiter {
+    fn new() -> FrameLimiter {
+        FrameLimiter { last_frame: Instant::now() }
+    }
+
+    // Sleeps out whatever is left of the frame's time budget, if a cap is set.
+    fn wait(&mut self, frame_cap: u32) {
+        if frame_cap > 0 {
+            let budget = Duration::from_secs_f64(1.0 / frame_cap as f64);
+            if let Some(remaining) = budget.checked_sub(self.last_frame.elapsed()) {
+                std::thread::sleep(remaining);
+            }
+        }
+        self.last_frame = Instant::now();
+    }
+}
+
+// Freezes the round behind a snapshot of its last frame until the player resumes.
+// The options submenu changes settings in place, so they apply as soon as play continues.
+async fn pause_menu(config: &mut GameConfig, assets: &Assets, boss_mix: f32, limiter: &mut FrameLimiter) {
+    let backdrop = Texture2D::from_image(&get_screen_data());
+    // The P press that opened the menu still counts as pressed until this frame ends, so let it pass
+    // before reading input or it would close the menu straight away.
+    next_frame().await;
+    // The highlighted setting while the options submenu is open.
+    let mut options: Option<usize> = None;
+    loop {
+        match options {
+            None => {
+                if is_key_pressed(KeyCode::P) {
+                    if let Err(e) = save_settings(config, SETTINGS_FILE) {
+                        eprintln!("Failed to save settings: {}", e);
+                    }
+                    return;
+                }
+                if is_key_pressed(KeyCode::O) { options = Some(0); }
+            }
+            Some(selected) => {
+                let count = Setting::ALL.len();
+                if is_key_pressed(KeyCode::Up) { options = Some((selected + count - 1) % count); }
+                if is_key_pressed(KeyCode::Down) { options = Some((selected + 1) % count); }
+                for (key, step) in [(KeyCode::Left, -1), (KeyCode::Right, 1)] {
+                    if is_key_pressed(key) {
+                        Setting::ALL[selected].adjust(config, step);
+                        assets.music.apply_volume(boss_mix, config);
+                    }
+                }
+                if is_key_pressed(KeyCode::O) || is_key_pressed(KeyCode::Backspace) { options = None; }
+            }
+        }
+
+        // Screen data is stored bottom-up, so the snapshot is drawn flipped.
+        draw_texture_ex(&backdrop, 0.0, 0.0, WHITE, DrawTextureParams { dest_size: Some(vec2(screen_width(), screen_height())), flip_y: true, ..Default::default() });
+        draw_rectangle(0.0, 0.0, screen_width(), screen_height(), Color::new(0.0, 0.0, 0.0, 0.6));
+        let (title, hint) = match options {
+            None => ("PAUSED", "P to resume, O for options"),
+            Some(_) => ("OPTIONS", "UP/DOWN to choose, LEFT/RIGHT to change, O to go back"),
+        };
+        // The options list can run long, so it starts near the top of the screen with the title above it.
+        let title_y = if options.is_some() { 70.0 } else { screen_height() / 2.0 - 100.0 };
+        let title_dims = measure_text(title, None, 60, 1.0);
+        draw_text(title, screen_width() / 2.0 - title_dims.width / 2.0, title_y, 60.0, WHITE);
+        let hint_y = match options {
+            Some(selected) => draw_settings(config, selected, 120.0) + 20.0,
+            None => screen_height() / 2.0 - 40.0,
+        };
+        let hint_dims = measure_text(hint, None, 20, 1.0);
+        draw_text(hint, screen_width() / 2.0 - hint_dims.width / 2.0, hint_y, 20.0, LIGHTGRAY);
+
+        limiter.wait(config.frame_cap);
+        next_frame().await
+    }
+}
+
+// Captures the current frame to a timestamped PNG in the screenshot folder and returns its path.
+fn save_screenshot() -> Result<String, String> {
+    std::fs::create_dir_all(SCREENSHOT_DIR).map_err(|e| e.to_string())?;
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map_err(|e| e.to_string())?;
+    let path = format!("{}/score_{}.png", SCREENSHOT_DIR, timestamp.as_millis());
+
+    // Screen data is stored bottom-up, so flip the rows before saving.
+    let frame = get_screen_data();
+    let row_len = frame.width as usize * 4;
+    let flipped: Vec<u8> = frame.bytes.chunks_exact(row_len).rev().flatten().copied().collect();
+    image::save_buffer(&path, &flipped, frame.width as u32, frame.height as u32, image::ColorType::Rgba8)
+        .map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+// How a round ended, for the results screen and high scores.
+struct RoundResult {
+    score: u32,
+    // Whether a score milestone was reached before the player lost any life.
+    flawless: bool,
+    // Whether aim assist bent any shot, which keeps the score off the high score table.
+    assisted: bool,
+}
+
+// Runs a single round of the game and reports how it ended.
+// Settings changed from the pause menu are written back to `live_config`.
+async fn play_game(live_config: &mut GameConfig, assets: &Assets, mode: GameMode, difficulty: Difficulty, best_score: u32) -> RoundResult {
+    let config: &GameConfig = live_config;
     let mut score = 0;
+    // The score shown on the HUD, which counts up toward the real score.
+    let mut displayed_score: f32 = 0.0;
 
     // Game object state setup.
     let entity_size = vec2(64.0, 64.0);
+    let spawn_pos = player_range_to_screen(config.player_spawn, entity_size, config.playfield());
     let mut player = Player {
-        pos: vec2(screen_width() * 0.5 - entity_size.x / 2.0, screen_height() - entity_size.y - 10.0),
+        pos: clamp_to_bounds(spawn_pos, entity_size, config.player_bounds, config.playfield()),
         size: entity_size,
     };
-    let player_speed = 700.0;
+    let player_speed = config.player_speed;
     let mut bullets: Vec<Bullet> = vec![];
     let bullet_speed = 800.0;
     let bullet_size = vec2(10.0, 20.0);
     let mut enemies: Vec<Enemy> = vec![];
-    let enemy_speed = 400.0;
     let enemy_size = entity_size;
-    let mut spawn_timer = 0.5;
-    let shoot_cooldown = 0.4;
+    let mut spawn_timer = config.spawn_grace_time;
+    let mut telegraphs: Vec<SpawnTelegraph> = vec![];
+    let mut formation_timer = config.formation_interval;
+    let mut loadout = Loadout::new();
     let mut shoot_timer = 0.0;
+    // Length of the cooldown started by the last shot, after any combo bonus.
+    let mut shot_cooldown = 0.0;
+    // Text and remaining time of the banner shown when the weapon changes.
+    let mut weapon_banner = String::new();
+    let mut weapon_banner_timer = 0.0;
+    let mut combo = 0;
+    let mut combo_timer = 0.0;
+    let mut elapsed = 0.0;
+    let mut enemy_bullets: Vec<EnemyBullet> = vec![];
+    let mut boss: Option<Boss> = None;
+    let mut boss_spawn_timer = config.boss_interval;
+    let mut wave = WaveState::new(config);
+    let mut popups: Vec<ScorePopup> = vec![];
+    // Popups pinned to the middle of the screen rather than to a spot in the playfield, drawn above the zoom.
+    let mut banners: Vec<ScorePopup> = vec![];
+    let mut dummy_stats = DummyStats::new();
+    let mut barriers: Vec<Barrier> = vec![];
+    let mut shockwaves: Vec<Shockwave> = vec![];
+    let mut gravity_wells: Vec<GravityWell> = vec![];
+    let mut bombs = config.starting_bombs;
+    // Remaining time of the screen flash shown when a bomb goes off.
+    let mut bomb_flash = 0.0;
+    // Remaining time of the red glow along the bottom edge when an enemy gets through at a cost.
+    let mut escape_flash = 0.0;
+    let mut next_milestone = 0;
+    let mut powerups: Vec<PowerUp> = vec![];
+    // The numbered formation currently in play, if any.
+    let mut kill_order: Option<KillOrder> = None;
+    // How far the background has scrolled, wrapped to one screen size.
+    let mut background_offset = Vec2::ZERO;
+    let mut lives = config.player_lives;
+    // Points banked toward the one revive a round can earn, and whether it has been spent.
+    let second_wind_enabled = config.second_wind_modes.contains(&mode);
+    let mut second_wind_charge = 0;
+    let mut second_wind_used = false;
+    // Seconds left of the grace period after losing a life, during which hits are ignored.
+    let mut invulnerable = 0.0;
+    // Stays true until the player first loses a life.
+    let mut flawless = true;
+    let mut flawless_milestone = false;
+    let mut assisted = false;
+    let mut replay = ReplayBuffer::new(config.death_replay_time);
+    // How far the music has faded from the normal track toward the boss track.
+    let mut boss_mix = 0.0;
+
+    let mut limiter = FrameLimiter::new();
+    let mut player_velocity = Vec2::ZERO;
+    // How precarious things are right now, from 0.0 (clear) to 1.0 (a threat on top of the player).
+    let mut danger = 0.0;
 
     // Main gameplay loop for one round.
     loop {
+        let config: &GameConfig = live_config;
         let dt = get_frame_time();
+        elapsed += dt;
+        let pacing = pacing_at(elapsed, config, difficulty);
+        let field = config.playfield();
 
         if shoot_timer > 0.0 {
             shoot_timer -= dt;
         }
 
-        // Handle player input and movement.
-        if is_key_down(KeyCode::Left) { player.pos.x -= player_speed * dt; }
-        if is_key_down(KeyCode::Right) { player.pos.x += player_speed * dt; }
-        if player.pos.x < 0.0 { player.pos.x = 0.0; }
-        if player.pos.x > screen_width() - player.size.x { player.pos.x = screen_width() - player.size.x; }
+        // Let gravity wells drag the ship before it moves under its own power. The drag is kept out of
+        // the ship's smoothed speed so it can't build up into momentum.
+        let drag = config.gravity_well_strength.min(player_speed * GRAVITY_WELL_MAX_DRAG);
+        let pulled = player.pos + gravity_pull(&gravity_wells, player.pos + player.size / 2.0, config) * drag * dt;
+        let pulled = clamp_to_bounds(pulled, player.size, config.player_bounds, config.playfield());
+        player.pos = resolve_movement(player.pos, pulled, |pos| get_hitbox(pos, player.size, 10.0), &barriers);
+
+        // Handle player input and movement, keeping the player out of barriers.
+        // Whatever stops the ship, an edge or a barrier, also takes away its smoothed speed.
+        let prev_pos = player.pos;
+        let mut input = Vec2::ZERO;
+        if is_key_down(KeyCode::Left) { input.x -= 1.0; }
+        if is_key_down(KeyCode::Right) { input.x += 1.0; }
+        if is_key_down(KeyCode::Up) { input.y -= 1.0; }
+        if is_key_down(KeyCode::Down) { input.y += 1.0; }
+        player_velocity = smooth_velocity(player_velocity, input * player_speed, config.movement_smoothing, dt);
+        player.pos += player_velocity * dt;
+        player.pos = clamp_to_bounds(player.pos, player.size, config.player_bounds, config.playfield());
+        player.pos = resolve_movement(prev_pos, player.pos, |pos| get_hitbox(pos, player.size, 10.0), &barriers);
+        if dt > 0.0 { player_velocity = (player.pos - prev_pos) / dt; }
+
+        // Practice tools: place a damage dummy, reset its stats, or leave.
+        if mode == GameMode::Practice {
+            if is_key_pressed(KeyCode::D) && !enemies.iter().any(|e| e.kind == EnemyKind::Dummy) {
+                let pos = vec2(field.center().x - enemy_size.x / 2.0, field.y + field.h * 0.25);
+                enemies.push(Enemy::new(EnemyKind::Dummy, pos, config));
+                dummy_stats.reset();
+            }
+            if is_key_pressed(KeyCode::R) { dummy_stats.reset(); }
+            if is_key_pressed(KeyCode::Escape) { return RoundResult { score, flawless: flawless_milestone, assisted }; }
+        }
+        if is_key_pressed(config.cycle_weapon_key) && loadout.owns_several() {
+            weapon_banner = format!("Weapon: {}", loadout.cycle().name());
+            weapon_banner_timer = 1.2;
+        }
+        // A bomb wipes out every enemy and enemy bullet on screen, without scoring them.
+        if is_key_pressed(KeyCode::B) && bombs > 0 {
+            bombs -= 1;
+            bomb_flash = 0.3;
+            // Thieves caught in the blast let go of what they were carrying.
+            release_carried(&enemies, enemy_size, &mut powerups);
+            enemies.retain(|e| e.kind == EnemyKind::Dummy);
+            enemy_bullets.clear();
+            play_sfx(&assets.explosion_sound, config.sfx_volume);
+        }
         if is_key_down(KeyCode::Space) && shoot_timer <= 0.0{
-            shoot_timer = shoot_cooldown;
-            play_sound_once(shoot_sound);
-            bullets.push(Bullet { pos: vec2(player.pos.x + player.size.x / 2.0 - bullet_size.x / 2.0, player.pos.y) });
+            let weapon = loadout.current();
+            // A running combo fires faster, so keeping it alive snowballs.
+            shot_cooldown = weapon.cooldown() * (1.0 - combo_fire_rate_bonus(combo, config));
+            shoot_timer = shot_cooldown;
+            play_sfx(&assets.shoot_sound, config.sfx_volume);
+            let muzzle = vec2(player.pos.x + player.size.x / 2.0 - bullet_size.x / 2.0, player.pos.y);
+            let mut shots = weapon.fire(muzzle, bullet_speed);
+            if config.aim_assist != AimAssist::Off {
+                let targets: Vec<Vec2> = enemies
+                    .iter()
+                    .map(|e| e.pos + enemy_size / 2.0)
+                    .chain(boss.iter().map(|b| b.pos + b.size / 2.0))
+                    .collect();
+                for shot in shots.iter_mut() { assisted |= config.aim_assist.nudge(shot, &targets); }
+            }
+            bullets.extend(shots);
+        }
+
+        // Update all object positions, bending bullets that pass near a gravity well.
+        for bullet in bullets.iter_mut() {
+            bullet.vel += gravity_pull(&gravity_wells, bullet.pos, config) * config.gravity_well_bullet_pull * dt;
+            bullet.pos += bullet.vel * dt;
+        }
+        for enemy in enemies.iter_mut() { enemy.update_movement(pacing.speed_multiplier, player.pos.y, config, dt); }
+        let scroll_scale = if config.background_scroll_ramps { pacing.speed_multiplier } else { 1.0 };
+        background_offset += config.background_scroll_direction.normalize_or_zero() * config.background_scroll_speed * scroll_scale * dt;
+        background_offset = vec2(background_offset.x.rem_euclid(screen_width()), background_offset.y.rem_euclid(screen_height()));
+        for bullet in enemy_bullets.iter_mut() {
+            bullet.vel += gravity_pull(&gravity_wells, bullet.pos, config) * config.gravity_well_bullet_pull * dt;
+            bullet.vel.y += bullet.gravity * dt;
+            bullet.pos += bullet.vel * dt;
+        }
+
+        // Bring in a boss on a timer, holding back regular spawns while it is alive.
+        if boss.is_none() && mode == GameMode::Classic {
+            boss_spawn_timer -= dt;
+            if boss_spawn_timer <= 0.0 {
+                boss = Some(Boss::new(config));
+                assets.music.play_stinger(config);
+            }
+        }
+        assets.music.crossfade(&mut boss_mix, boss.is_some(), dt, config);
+        if let Some(boss) = boss.as_mut() {
+            let cues = boss.update(dt, config, &mut enemy_bullets);
+            if cues.enraged || cues.phase_changed {
+                play_sfx(&assets.enrage_sound, config.sfx_volume);
+            }
         }
 
-        // Update all object positions.
-        for bullet in bullets.iter_mut() { bullet.pos.y -= bullet_speed * dt; }
-        for enemy in enemies.iter_mut() { enemy.pos.y += enemy_speed * dt; }
+        // In wave mode, award the speed bonus once the field is clear and then start the next wave.
+        if mode == GameMode::Waves
+            && let Some(clear) = wave.update(dt, enemies.is_empty() && telegraphs.is_empty(), config)
+        {
+            score += clear.bonus;
+            let text = if clear.bonus > 0 { format!("SPEED BONUS +{}", clear.bonus) } else { format!("WAVE {} CLEAR", clear.number) };
+            banners.push(ScorePopup::new(vec2(screen_width() / 2.0, screen_height() / 2.0), text, GOLD, 40.0));
+        }
 
-        // Spawn new enemies on a timer.
+        // Telegraph new enemies on a timer, then spawn them once the warning runs out.
         spawn_timer -= dt;
-        if spawn_timer <= 0.0 {
-            spawn_timer = 1.5;
-            enemies.push(Enemy { pos: vec2(rand::gen_range(0.0, screen_width() - enemy_size.x), -enemy_size.y) });
+        formation_timer -= dt;
+        let mut incoming = vec![];
+        let can_spawn = match mode {
+            GameMode::Classic => boss.is_none(),
+            GameMode::Waves => wave.can_spawn(),
+            GameMode::Practice => false,
+        };
+        if spawn_timer <= 0.0 && can_spawn {
+            spawn_timer = pacing.spawn_interval;
+            let pos = vec2(rand::gen_range(field.left(), (field.right() - enemy_size.x).max(field.left())), field.top() - enemy_size.y);
+            let weights = config.enemy_weights.get(&mode).map_or(&[][..], Vec::as_slice);
+            incoming.push(Enemy::new(pick_enemy_kind(weights), pos, config));
+            wave.on_spawn();
+        }
+        if formation_timer <= 0.0 && boss.is_none() && mode == GameMode::Classic {
+            formation_timer = config.formation_interval;
+            let roll = rand::gen_range(0.0, 1.0);
+            let formation = if roll < config.formation_side_chance {
+                side_formation(config, enemy_size, if rand::gen_range(0, 2) == 0 { Edge::Left } else { Edge::Right })
+            } else if roll < config.formation_side_chance + config.ordered_formation_chance && kill_order.is_none() {
+                kill_order = Some(KillOrder::new(config.formation_size));
+                numbered_formation(config, enemy_size)
+            } else {
+                sine_formation(config, enemy_size)
+            };
+            incoming.extend(formation);
+        }
+        if !incoming.is_empty() && let Some(sound) = &assets.spawn_warning_sound {
+            play_sfx(sound, config.sfx_volume * SPAWN_WARNING_VOLUME);
+        }
+        telegraphs.extend(incoming.into_iter().map(|enemy| SpawnTelegraph { enemy, timer: config.telegraph_time }));
+        for telegraph in telegraphs.iter_mut() { telegraph.timer -= dt; }
+        let (arrived, waiting): (Vec<_>, Vec<_>) = telegraphs.into_iter().partition(|t| t.timer <= 0.0);
+        telegraphs = waiting;
+        enemies.extend(arrived.into_iter().map(|t| t.enemy));
+
+        update_healers(&mut enemies, enemy_size, config, dt);
+        barriers.extend(update_builders(&mut enemies, enemy_size, get_hitbox(player.pos, player.size, 10.0), config, dt));
+        update_lobbers(&mut enemies, enemy_size, player.pos + player.size / 2.0, config, &mut enemy_bullets, dt);
+        shockwaves.extend(update_shockers(&mut enemies, enemy_size, config, dt));
+        update_shockwaves(&mut shockwaves, config, dt);
+        gravity_wells.extend(update_warpers(&mut enemies, enemy_size, config));
+        update_gravity_wells(&mut gravity_wells, dt);
+        update_barriers(&mut barriers, dt);
+        block_bullets(&mut bullets, &mut barriers, bullet_size);
+
+        // Process collisions and update score, chaining kills into a combo.
+        let enemy_hits = handle_collisions(&mut bullets, &mut enemies, bullet_size, enemy_size, &assets.explosion_sound, config);
+        for hit in enemy_hits.iter().filter(|h| h.kind == EnemyKind::Dummy) {
+            dummy_stats.record(get_time(), hit.damage);
+        }
+        if config.show_damage_numbers {
+            for hit in enemy_hits.iter().filter(|h| h.damage > 0) {
+                popups.push(ScorePopup::new(hit.pos, format!("-{}", hit.damage), DAMAGE_NUMBER_COLOR, 22.0));
+            }
+        }
+        let hits = enemy_hits.iter().filter(|h| h.killed).count() as u32;
+
+        // Check kills from a numbered formation against its order, paying out when it completes.
+        for (number, pos) in enemy_hits.iter().filter(|h| h.killed).filter_map(|h| Some((h.sequence?, h.pos))) {
+            let Some(order) = kill_order.as_mut() else { break };
+            match order.record(number) {
+                KillOrderResult::Progress => {}
+                KillOrderResult::Complete => {
+                    score += config.ordered_formation_bonus;
+                    popups.push(ScorePopup::new(pos, format!("IN ORDER! +{}", config.ordered_formation_bonus), GOLD, 32.0));
+                    kill_order = None;
+                }
+                KillOrderResult::Broken => {
+                    score = score.saturating_sub(config.ordered_formation_penalty);
+                    popups.push(ScorePopup::new(pos, format!("WRONG ORDER -{}", config.ordered_formation_penalty), RED, 32.0));
+                    abandon_kill_order(&mut kill_order, &mut enemies);
+                }
+            }
+        }
+        // The sequence is abandoned once its enemies have all left the field.
+        let numbered_left = enemies.iter().chain(telegraphs.iter().map(|t| &t.enemy)).any(|e| e.sequence.is_some());
+        if !numbered_left { kill_order = None; }
+
+        // Destroyed enemies sometimes drop a bomb, collected by flying into it, and a thief always drops the one it stole.
+        for hit in enemy_hits.iter().filter(|h| h.killed && h.kind != EnemyKind::Decoy) {
+            if hit.carrying || rand::gen_range(0.0, 1.0) < config.powerup_drop_chance {
+                powerups.push(PowerUp { pos: hit.pos + vec2(0.0, enemy_size.y / 2.0) });
+            }
+        }
+        update_powerups(&mut powerups, config.powerup_fall_speed, field, dt);
+        update_thieves(&mut enemies, enemy_size, &mut powerups, config, dt);
+        let player_box = get_hitbox(player.pos, player.size, 10.0);
+        let collected = powerups.len();
+        powerups.retain(|p| !p.hitbox().overlaps(&player_box));
+        for _ in powerups.len()..collected {
+            bombs += 1;
+            popups.push(ScorePopup::new(player.pos + vec2(player.size.x / 2.0, 0.0), "+1 BOMB".to_string(), POWERUP_COLOR, 24.0));
+        }
+        let mut boss_defeated = false;
+        let mut boss_center = Vec2::ZERO;
+        if let Some(b) = boss.as_mut() {
+            let boss_hits = handle_boss_hits(&mut bullets, b, bullet_size);
+            if config.show_damage_numbers && boss_hits > 0 {
+                popups.push(ScorePopup::new(b.pos + vec2(b.size.x / 2.0, b.size.y), format!("-{}", boss_hits), DAMAGE_NUMBER_COLOR, 22.0));
+            }
+            if b.health == 0 {
+                play_sfx(&assets.explosion_sound, config.sfx_volume);
+                boss_center = b.pos + b.size / 2.0;
+                boss = None;
+                boss_spawn_timer = config.boss_interval;
+                boss_defeated = true;
+                if let Some(weapon) = loadout.unlock_next() {
+                    weapon_banner = format!("New weapon: {} ({:?} to switch)", weapon.name(), config.cycle_weapon_key);
+                    weapon_banner_timer = 2.5;
+                }
+            }
         }
+        if hits > 0 || boss_defeated {
+            combo += hits + boss_defeated as u32;
+            combo_timer = config.combo_window;
+        } else if combo_timer > 0.0 {
+            // The combo drains slower while a boss is up, so gaps between its attacks don't break it.
+            let decay_rate = if boss.is_some() { config.boss_combo_decay_rate } else { 1.0 };
+            combo_timer -= dt * decay_rate;
+            if combo_timer <= 0.0 { combo = 0; }
+        }
+        let multiplier = combo_multiplier(combo, config);
+        let kill_points: u32 = enemy_hits.iter().filter(|h| h.killed).map(|h| h.points).sum();
+        score += kill_points * multiplier;
+        if boss_defeated {
+            score += config.boss_points * multiplier;
+            popups.push(ScorePopup::new(boss_center, format!("+{}", config.boss_points * multiplier), GOLD, 36.0));
+        }
+        // Scoring on a strong combo builds toward a second wind.
+        if second_wind_enabled && !second_wind_used && multiplier >= config.second_wind_min_multiplier {
+            let earned = (kill_points + if boss_defeated { config.boss_points } else { 0 }) * multiplier;
+            second_wind_charge = (second_wind_charge + earned).min(config.second_wind_charge);
+        }
+
+        // In classic mode, hand out the reward for each score milestone crossed.
+        if mode == GameMode::Classic {
+            for milestone in reached_milestones(&config.milestones, &mut next_milestone, score) {
+                let reward = match milestone.reward {
+                    MilestoneReward::WeaponUpgrade => loadout.unlock_next().map(|w| format!("{} unlocked", w.name())),
+                    MilestoneReward::Bomb => None,
+                };
+                // Once every weapon is owned, an upgrade milestone pays out a bomb instead.
+                let reward = reward.unwrap_or_else(|| { bombs += 1; "+1 BOMB".to_string() });
+                let mut text = format!("{} POINTS: {}", milestone.score, reward);
+                if flawless {
+                    flawless_milestone = true;
+                    score += config.flawless_milestone_bonus;
+                    text += &format!(", FLAWLESS +{}", config.flawless_milestone_bonus);
+                }
+                banners.push(ScorePopup::new(vec2(screen_width() / 2.0, screen_height() / 2.0 - 100.0), text, SKYBLUE, 36.0));
+            }
+        }
+        update_popups(&mut popups, dt);
+        update_popups(&mut banners, dt);
+
+        // Record this frame for the death replay.
+        replay.push(ReplayFrame {
+            time: elapsed,
+            player: player.pos,
+            enemies: enemies.iter().map(|e| (e.pos, e.kind, e.tint())).collect(),
+            bullets: bullets.iter().map(|b| b.pos).collect(),
+            enemy_bullets: enemy_bullets.iter().map(|b| (b.pos, enemy_bullet_color(b))).collect(),
+            boss: boss.as_ref().map(|b| Rect::new(b.pos.x, b.pos.y, b.size.x, b.size.y)),
+            boss_laser: boss.as_ref().and_then(Boss::laser_beam),
+            shockwaves: shockwaves.clone(),
+            gravity_wells: gravity_wells.clone(),
+        });
 
-        // Process collisions and update score.
-        let hits = handle_collisions(&mut bullets, &mut enemies, bullet_size, enemy_size, explosion_sound);
-        score += hits;
+        // Charge for enemies that got off the bottom alive, according to the mode's rule.
+        // Killed enemies are moved off the bottom too, but with no health left. Decoys are meant to be dodged, so they never count.
+        let escaped: Vec<Vec2> = enemies
+            .iter()
+            .filter(|e| e.pos.y >= field.bottom() && e.health > 0 && !matches!(e.kind, EnemyKind::Decoy | EnemyKind::Dummy))
+            .map(|e| e.pos + enemy_size / 2.0)
+            .collect();
+        let mut breached = false;
+        if !escaped.is_empty() {
+            match config.escape_rules.get(&mode).copied().unwrap_or(EscapeRule::Free) {
+                EscapeRule::Free => {}
+                EscapeRule::ScorePenalty => {
+                    let penalty = config.escape_penalty;
+                    for center in &escaped {
+                        score = score.saturating_sub(penalty);
+                        popups.push(ScorePopup::new(vec2(center.x, field.bottom() - 40.0), format!("-{}", penalty), RED, 28.0));
+                    }
+                    escape_flash = 0.4;
+                }
+                EscapeRule::LoseLife => {
+                    breached = true;
+                    escape_flash = 0.8;
+                }
+            }
+        }
 
-        // Check for game over condition.
+        // Check whether the player was hit. A hit or an escape under the life-loss rule costs a life,
+        // breaks the combo and a flawless run; losing the last life replays the final moment before ending the round.
+        invulnerable = (invulnerable - dt).max(0.0);
         let player_hitbox = get_hitbox(player.pos, player.size, 10.0);
-        for enemy in &enemies {
-            let enemy_hitbox = get_hitbox(enemy.pos, enemy_size, 8.0);
-            if player_hitbox.overlaps(&enemy_hitbox) {
-                play_sound_once(game_over_sound);
-                return score; // End the game and return the score.
+        let touches_player = |e: &Enemy| e.kind != EnemyKind::Dummy && player_hitbox.overlaps(&e.hitbox(enemy_size));
+        let hit_by_enemy = enemies.iter().any(touches_player);
+        let hit_by_bullet = enemy_bullets.iter().any(|b| player_hitbox.overlaps(&enemy_bullet_rect(b)));
+        let player_reach = player_hitbox.w.min(player_hitbox.h) / 2.0;
+        let hit_by_boss = boss.as_ref().is_some_and(|b| player_hitbox.overlaps(&b.hitbox()) || b.laser_hits(player_hitbox.center(), player_reach));
+        let hit_by_shockwave = shockwaves.iter().any(|s| s.hits(player_hitbox.center(), player_reach, config));
+        if (hit_by_enemy || hit_by_bullet || hit_by_boss || hit_by_shockwave || breached) && invulnerable <= 0.0 {
+            lives -= 1;
+            flawless = false;
+            combo = 0;
+            combo_timer = 0.0;
+            // A full second wind charge turns the last lost life into a revive: every life comes back and
+            // a blast clears the screen of enemies and their attacks.
+            if lives == 0 && second_wind_enabled && !second_wind_used && second_wind_charge >= config.second_wind_charge {
+                second_wind_used = true;
+                lives = config.player_lives;
+                bomb_flash = 0.3;
+                // As with a bomb, thieves caught in the blast let go of what they were carrying.
+                release_carried(&enemies, enemy_size, &mut powerups);
+                enemies.retain(|e| e.kind == EnemyKind::Dummy);
+                enemy_bullets.clear();
+                shockwaves.clear();
+                gravity_wells.clear();
+                banners.push(ScorePopup::new(vec2(screen_width() / 2.0, screen_height() / 2.0), "SECOND WIND!".to_string(), GOLD, 48.0));
             }
+            if lives == 0 {
+                play_sfx(&assets.game_over_sound, config.sfx_volume);
+                play_death_replay(&replay, assets, config, player.size, enemy_size, bullet_size).await;
+                return RoundResult { score, flawless: flawless_milestone, assisted };
+            }
+            play_sfx(&assets.explosion_sound, config.sfx_volume);
+            invulnerable = config.invulnerable_time;
+            let rammed_numbered = enemies.iter().any(|e| e.sequence.is_some() && touches_player(e));
+            enemies.retain(|e| !touches_player(e));
+            if rammed_numbered { abandon_kill_order(&mut kill_order, &mut enemies); }
+            enemy_bullets.retain(|b| !player_hitbox.overlaps(&enemy_bullet_rect(b)));
+        }
+
+        // Remove off-screen enemies and bullets.
+        // Off-screen here means outside the playfield, so nothing lingers in the margins.
+        // Enemies that drift out a side are dropped before they can fall off the bottom and count as escapes.
+        let on_field = |enemy: &Enemy| enemy.pos.y < field.bottom() && !enemy.left_sideways(enemy_size, field);
+        // Shot enemies are culled here as well, but only a numbered one that got away alive spoils the order.
+        let numbered_escaped = enemies.iter().any(|e| e.sequence.is_some() && e.health > 0 && !on_field(e));
+        if mode == GameMode::Waves {
+            let got_away = enemies.iter().filter(|e| e.health > 0 && !matches!(e.kind, EnemyKind::Decoy | EnemyKind::Dummy) && !on_field(e)).count();
+            wave.on_escape(got_away as u32);
         }
+        enemies.retain(on_field);
+        if numbered_escaped { abandon_kill_order(&mut kill_order, &mut enemies); }
+        bullets.retain(|b| b.pos.y > field.top() - bullet_size.y && b.pos.x > field.left() - bullet_size.x && b.pos.x < field.right());
+        enemy_bullets.retain(|b| b.pos.y < field.bottom() + ENEMY_BULLET_RADIUS && b.pos.x > field.left() - ENEMY_BULLET_RADIUS && b.pos.x < field.right() + ENEMY_BULLET_RADIUS);
+
+        // Advance the timers and counters that only affect what is drawn.
+        // The danger meter jumps up as threats close in but settles back slowly once they pass.
+        let nearest_threat = nearest_threat_distance(player.pos + player.size / 2.0, &enemies, &enemy_bullets, boss.as_ref(), enemy_size);
+        let target_danger = nearest_threat.map_or(0.0, |d| 1.0 - (d / DANGER_RANGE).clamp(0.0, 1.0));
+        let danger_rate = if target_danger > danger { 12.0 } else { 2.0 };
+        danger += (target_danger - danger) * (1.0 - (-danger_rate * dt).exp());
+        bomb_flash = (bomb_flash - dt).max(0.0);
+        escape_flash = (escape_flash - dt).max(0.0);
+        weapon_banner_timer = (weapon_banner_timer - dt).max(0.0);
+        displayed_score += (score as f32 - displayed_score) * (1.0 - (-SCORE_COUNT_RATE * dt).exp());
+        if (score as f32 - displayed_score).abs() < 0.5 { displayed_score = score as f32; }
+        let dps = dummy_stats.dps(get_time(), config.dummy_dps_window);
 
-        // Remove off-screen enemies.
-        enemies.retain(|enemy| enemy.pos.y < screen_height());
+        // Queue everything for drawing, then draw it back to front by layer.
+        let camera = world_camera(config.camera_zoom, player.pos + player.size / 2.0);
+        let mut draws = DrawQueue::new();
+        draws.push(Layer::Background, || {
+            draw_scrolling_background(&assets.background_texture, background_offset, WHITE);
+            if let Some(tint) = combo_tint(multiplier).filter(|_| !config.reduce_effects) {
+                draw_rectangle(0.0, 0.0, screen_width(), screen_height(), tint);
+            }
+            // Shade the margins and outline the playfield they frame.
+            if config.has_margins() {
+                let shade = Color::new(0.0, 0.0, 0.0, 0.5);
+                draw_rectangle(0.0, 0.0, screen_width(), field.top(), shade);
+                draw_rectangle(0.0, field.bottom(), screen_width(), screen_height() - field.bottom(), shade);
+                draw_rectangle(0.0, field.top(), field.left(), field.h, shade);
+                draw_rectangle(field.right(), field.top(), screen_width() - field.right(), field.h, shade);
+                draw_rectangle_lines(field.x, field.y, field.w, field.h, 2.0, Color::new(1.0, 1.0, 1.0, 0.25));
+            }
+        });
+        draws.push(Layer::Ground, || {
+            draw_gravity_wells(&gravity_wells, config);
+            draw_barriers(&barriers, config);
+            if config.show_firing_line {
+                let muzzle = vec2(player.pos.x + player.size.x / 2.0, player.pos.y);
+                for shot in loadout.current().fire(muzzle, screen_height()) {
+                    draw_line(muzzle.x, muzzle.y, muzzle.x + shot.vel.x, muzzle.y + shot.vel.y, 2.0, Color::new(1.0, 0.4, 0.4, 0.18));
+                }
+            }
+        });
+        draws.push(Layer::Enemies, || {
+            draw_enemy_trails(&enemies, enemy_size, &assets.enemy_texture);
+            for powerup in &powerups { draw_powerup_shape(powerup.pos, POWERUP_COLOR); }
+            for enemy in &enemies {
+                if enemy.kind == EnemyKind::Decoy {
+                    draw_powerup_shape(enemy.pos + enemy_size / 2.0, decoy_color(config));
+                } else {
+                    draw_texture_ex(&assets.enemy_texture, enemy.pos.x, enemy.pos.y, enemy.tint(), DrawTextureParams { dest_size: Some(enemy_size), ..Default::default() });
+                }
+            }
+            draw_enemy_effects(&enemies, enemy_size, config);
+            draw_shockwaves(&shockwaves, config);
+            for enemy in &enemies {
+                if let Some(number) = enemy.sequence {
+                    let text = (number + 1).to_string();
+                    let dims = measure_text(&text, None, 28, 1.0);
+                    let center = enemy.pos + enemy_size / 2.0;
+                    draw_text(&text, center.x - dims.width / 2.0, center.y + dims.height / 2.0, 28.0, YELLOW);
+                }
+            }
+            if let Some(boss) = &boss {
+                boss.draw(&assets.boss_texture);
+            }
+        });
+        draws.push(Layer::Player, || {
+            // Blink while invulnerable after losing a life.
+            let visible = invulnerable <= 0.0 || (get_time() * 10.0) as i32 % 2 == 0;
+            if visible {
+                draw_texture_ex(&assets.player_texture, player.pos.x, player.pos.y, WHITE, DrawTextureParams { dest_size: Some(player.size), ..Default::default() });
+            }
+            // While the combo speeds up firing, a bar under the ship shows the shot recharging.
+            if combo_fire_rate_bonus(combo, config) > 0.0 && shot_cooldown > 0.0 {
+                let fill = 1.0 - (shoot_timer / shot_cooldown).clamp(0.0, 1.0);
+                let bar_y = player.pos.y + player.size.y + 4.0;
+                draw_rectangle(player.pos.x, bar_y, player.size.x, 4.0, Color::new(0.3, 0.3, 0.3, 0.6));
+                draw_rectangle(player.pos.x, bar_y, player.size.x * fill, 4.0, ORANGE);
+            }
+        });
+        draws.push(Layer::Projectiles, || {
+            if let Some((start, end, telegraphed)) = boss.as_ref().and_then(Boss::laser_beam) {
+                draw_laser_beam(start, end, telegraphed);
+            }
+            for bullet in &bullets { draw_rectangle(bullet.pos.x, bullet.pos.y, bullet_size.x, bullet_size.y, RED); }
+            for bullet in &enemy_bullets {
+                draw_circle(bullet.pos.x, bullet.pos.y, ENEMY_BULLET_RADIUS, enemy_bullet_color(bullet));
+            }
+        });
 
-        // Draw everything to the screen.
-        draw_texture_ex(background_texture, 0.0, 0.0, WHITE, DrawTextureParams {
-            dest_size: Some(vec2(screen_width(), screen_height())),
-            ..Default::default()
+        // Flash when a bomb goes off or the boss enters a new phase, and telegraph its enrage with a red flash and banner.
+        // A costly escape lights the bottom edge red.
+        if !config.reduce_effects {
+            draws.push(Layer::Flash, || {
+                if escape_flash > 0.0 {
+                    for i in 0..6 {
+                        let band = 12.0 * (i + 1) as f32;
+                        draw_rectangle(field.x, field.bottom() - band, field.w, band, Color::new(1.0, 0.1, 0.1, escape_flash * 0.12));
+                    }
+                }
+                if bomb_flash > 0.0 {
+                    draw_rectangle(0.0, 0.0, screen_width(), screen_height(), Color::new(1.0, 1.0, 1.0, bomb_flash * 2.0));
+                }
+                if let Some(boss) = boss.as_ref().filter(|b| b.phase_flash > 0.0) {
+                    draw_rectangle(0.0, 0.0, screen_width(), screen_height(), Color::new(1.0, 1.0, 1.0, boss.phase_flash * 0.4));
+                }
+                if let Some(boss) = boss.as_ref().filter(|b| b.enrage_flash > 0.0) {
+                    draw_rectangle(0.0, 0.0, screen_width(), screen_height(), Color::new(1.0, 0.0, 0.0, boss.enrage_flash * 0.3));
+                }
+            });
+        }
+        draws.push(Layer::Overlay, || {
+            draw_popups(&popups);
+        });
+        // Markers, banners and the boss bar belong to the screen, so they are drawn outside the zoom.
+        // Markers follow the playfield's visible edges and the points they warn about.
+        draws.push(Layer::Hud, || {
+            if config.show_spawn_indicators {
+                let top_left = camera.world_to_screen(field.point());
+                let bottom_right = camera.world_to_screen(field.point() + field.size());
+                let visible_min = top_left.max(Vec2::ZERO);
+                let visible_max = bottom_right.min(vec2(screen_width(), screen_height()));
+                let visible = Rect::new(visible_min.x, visible_min.y, visible_max.x - visible_min.x, visible_max.y - visible_min.y);
+                let pulse = (get_time() as f32 * 12.0).sin() * 0.25 + 0.75;
+                for telegraph in telegraphs.iter().filter(|t| t.enemy.formation.is_none_or(|s| s.entry_edge().is_none())) {
+                    let x = camera.world_to_screen(telegraph.enemy.pos + enemy_size / 2.0).x;
+                    let y = visible.top();
+                    draw_triangle(vec2(x - 14.0, y + 4.0), vec2(x + 14.0, y + 4.0), vec2(x, y + 22.0), Color::new(1.0, 0.3, 0.2, pulse));
+                }
+                // Side formations get one arrow per edge, sized by the group entering there.
+                for edge in [Edge::Left, Edge::Right] {
+                    let entering: Vec<_> = telegraphs.iter().filter(|t| t.enemy.formation.is_some_and(|s| s.entry_edge() == Some(edge))).collect();
+                    if let Some(first) = entering.first() {
+                        let y = camera.world_to_screen(first.enemy.pos + enemy_size / 2.0).y.clamp(visible.top(), visible.bottom());
+                        draw_entry_arrow(edge, y, entering.len(), visible);
+                    }
+                }
+            }
+            if let Some(boss) = &boss {
+                boss.draw_health_bar();
+            }
+            if boss.as_ref().is_some_and(|b| b.enrage_flash > 0.0) {
+                let text = "ENRAGED!";
+                let text_dims = measure_text(text, None, 60, 1.0);
+                draw_text(text, screen_width() / 2.0 - text_dims.width / 2.0, screen_height() / 2.0, 60.0, RED);
+            }
+            draw_popups(&banners);
+            if mode == GameMode::Waves && wave.break_timer > 0.0 {
+                let text = format!("WAVE {}", wave.number + 1);
+                let text_dims = measure_text(&text, None, 60, 1.0);
+                draw_text(&text, screen_width() / 2.0 - text_dims.width / 2.0, screen_height() / 2.0 - 60.0, 60.0, WHITE);
+            }
+            if weapon_banner_timer > 0.0 {
+                let banner_dims = measure_text(&weapon_banner, None, 24, 1.0);
+                draw_text(&weapon_banner, screen_width() / 2.0 - banner_dims.width / 2.0, screen_height() - 100.0, 24.0, SKYBLUE);
+            }
+        });
+        draws.push(Layer::Hud, || {
+            if mode == GameMode::Practice {
+                if let Some(dummy) = enemies.iter().find(|e| e.kind == EnemyKind::Dummy) {
+                    let text = format!("Damage: {}  DPS: {:.1}", dummy_stats.total(), dps);
+                    let text_dims = measure_text(&text, None, 24, 1.0);
+                    draw_text(&text, dummy.pos.x + enemy_size.x / 2.0 - text_dims.width / 2.0, dummy.pos.y - 12.0, 24.0, WHITE);
+                }
+                draw_text("D: place dummy   R: reset stats   ESC: end practice", 20.0, screen_height() - 20.0, 20.0, LIGHTGRAY);
+            }
+            // The score counts up smoothly toward its real value.
+            draw_text(&format!("Score: {}", format_score(displayed_score.round() as u32, config.score_style)), 20.0, 30.0, 30.0, WHITE);
+            let fire_rate_bonus = combo_fire_rate_bonus(combo, config);
+            if multiplier > 1 || fire_rate_bonus > 0.0 {
+                draw_text(&format!("Combo x{}  Fire rate +{:.0}%", multiplier, fire_rate_bonus * 100.0), 20.0, 60.0, 24.0, ORANGE);
+            }
+            if bombs > 0 {
+                draw_text(&format!("Bombs: {} (B)", bombs), 20.0, 90.0, 24.0, SKYBLUE);
+            }
+            let best_text = format!("Best: {}", format_score(best_score.max(score), config.score_style));
+            let best_dims = measure_text(&best_text, None, 24, 1.0);
+            draw_text(&best_text, screen_width() - best_dims.width - 20.0, 30.0, 24.0, WHITE);
+            let lives_text = format!("Lives: {}", lives);
+            let lives_dims = measure_text(&lives_text, None, 24, 1.0);
+            draw_text(&lives_text, screen_width() - lives_dims.width - 20.0, 60.0, 24.0, WHITE);
+            if flawless {
+                let flawless_dims = measure_text("FLAWLESS", None, 20, 1.0);
+                draw_text("FLAWLESS", screen_width() - flawless_dims.width - 20.0, 85.0, 20.0, Color::new(1.0, 0.85, 0.3, 0.6));
+            }
+            if second_wind_enabled && !second_wind_used {
+                let fill = second_wind_charge as f32 / config.second_wind_charge.max(1) as f32;
+                let (label, color) = if fill >= 1.0 { ("SECOND WIND READY", GOLD) } else { ("SECOND WIND", LIGHTGRAY) };
+                let label_dims = measure_text(label, None, 18, 1.0);
+                draw_text(label, 20.0, 115.0, 18.0, color);
+                draw_rectangle(30.0 + label_dims.width, 104.0, 100.0, 10.0, Color::new(0.2, 0.2, 0.2, 0.8));
+                draw_rectangle(30.0 + label_dims.width, 104.0, 100.0 * fill.min(1.0), 10.0, GOLD);
+            }
+            if config.show_danger_meter {
+                let (width, x) = (120.0, screen_width() - 140.0);
+                let color = Color::new((danger * 2.0).min(1.0), (2.0 - danger * 2.0).min(1.0), 0.2, 1.0);
+                draw_text("DANGER", x - 70.0, 112.0, 20.0, LIGHTGRAY);
+                draw_rectangle(x, 100.0, width, 12.0, Color::new(0.2, 0.2, 0.2, 0.8));
+                draw_rectangle(x, 100.0, width * danger, 12.0, color);
+            }
+            draw_mute_icon(config);
         });
-        draw_texture_ex(player_texture, player.pos.x, player.pos.y, WHITE, DrawTextureParams { dest_size: Some(player.size), ..Default::default() });
-        for bullet in &bullets { draw_rectangle(bullet.pos.x, bullet.pos.y, bullet_size.x, bullet_size.y, RED); }
-        for enemy in &enemies { draw_texture_ex(enemy_texture, enemy.pos.x, enemy.pos.y, WHITE, DrawTextureParams { dest_size: Some(enemy_size), ..Default::default() }); }
+        draws.flush(&camera);
 
-        // Draw the current score.
-        draw_text(&format!("Score: {}", score), 20.0, 30.0, 30.0, WHITE);
+        // Mute or unmute everything on request, then pause if asked, holding the round still until the player resumes.
+        if is_key_pressed(KeyCode::N) {
+            toggle_mute(live_config);
+            assets.music.apply_volume(boss_mix, live_config);
+        }
+        if is_key_pressed(KeyCode::P) {
+            pause_menu(live_config, assets, boss_mix, &mut limiter).await;
+        } else {
+            limiter.wait(live_config.frame_cap);
+        }
 
         next_frame().await
     }
@@ -136,29 +1659,45 @@ async fn play_game(
 // Manages the overall application state (playing -> game over -> playing).
 #[macroquad::main("Shooter Game")]
 async fn main() {
+    let mut config = GameConfig::default();
+    load_settings(&mut config, SETTINGS_FILE);
+    for (mode, weights) in &config.enemy_weights {
+        if let Some(problem) = enemy_weights_problem(weights) {
+            eprintln!("Enemy weights for {} mode: {}; spawning basic enemies only", mode.name(), problem);
+        }
+    }
+
     // Load all assets once at the start.
-    let player_texture = load_texture("assets/player.png").await.unwrap();
-    let enemy_texture = load_texture("assets/enemy.png").await.unwrap();
-    let shoot_sound = load_sound("assets/shoot.wav").await.unwrap();
-    let explosion_sound = load_sound("assets/short_explode.wav").await.unwrap();
-    let game_over_sound = load_sound("assets/game_over.wav").await.unwrap();
-    let background_texture = load_texture("assets/background_2.png").await.unwrap();
+    let assets = Assets::load().await;
+
+    let mut mode = GameMode::Classic;
+    let mut difficulty = Difficulty::Normal;
+    let mut high_scores = HighScores::load(HIGH_SCORE_FILE);
 
     // The main application loop.
     loop {
         // Start a game round and wait for it to end, capturing the final score.
-        let final_score = play_game(&player_texture, &enemy_texture, &shoot_sound, &explosion_sound, &game_over_sound, &background_texture).await;
+        let (played_mode, played_difficulty) = (mode, difficulty);
+        let best_score = high_scores.get(played_mode, played_difficulty);
+        assets.music.start(&config);
+        let result = play_game(&mut config, &assets, played_mode, played_difficulty, best_score).await;
+        let final_score = result.score;
+        assets.music.stop();
+        let new_best = played_mode.records_scores() && !result.assisted && high_scores.record(played_mode, played_difficulty, final_score, result.flawless);
 
         // Display the "Game Over" screen until the user restarts.
+        let mut save_message = String::new();
+        let mut save_message_timer = 0.0;
+        let mut limiter = FrameLimiter::new();
         loop {
             // Draw the background and overlay.
-            draw_texture_ex(&background_texture, 0.0, 0.0, WHITE, DrawTextureParams { dest_size: Some(vec2(screen_width(), screen_height())), ..Default::default() });
+            draw_texture_ex(&assets.background_texture, 0.0, 0.0, WHITE, DrawTextureParams { dest_size: Some(vec2(screen_width(), screen_height())), ..Default::default() });
             draw_rectangle(0.0, 0.0, screen_width(), screen_height(), Color::new(0.0, 0.0, 0.0, 0.7));
 
             // Draw text elements.
             let text = "GAME OVER";
-            let text2 = "Press ENTER to play again";
-            let final_score_text = format!("Final Score: {}", final_score);
+            let text2 = "Press ENTER to play again, S to save a screenshot";
+            let final_score_text = format!("Final Score: {}", format_score(final_score, config.score_style));
 
             let text_dims = measure_text(text, None, 80, 1.0);
             draw_text(text, screen_width() / 2.0 - text_dims.width / 2.0, screen_height() / 2.0 - 40.0, 80.0, WHITE);
@@ -166,15 +1705,92 @@ async fn main() {
             let text_dims2 = measure_text(&final_score_text, None, 40, 1.0);
             draw_text(&final_score_text, screen_width() / 2.0 - text_dims2.width / 2.0, screen_height() / 2.0 + 40.0, 40.0, WHITE);
 
+            // Show the record for the mode and difficulty selected for the next round.
+            let best_text = if new_best && mode == played_mode && difficulty == played_difficulty {
+                "NEW BEST!".to_string()
+            } else {
+                let badge = if high_scores.is_flawless(mode, difficulty) { " FLAWLESS" } else { "" };
+                format!("Best ({}, {}): {}{}", mode.name(), difficulty.name(), format_score(high_scores.get(mode, difficulty), config.score_style), badge)
+            };
+            let best_dims = measure_text(&best_text, None, 24, 1.0);
+            draw_text(&best_text, screen_width() / 2.0 - best_dims.width / 2.0, screen_height() / 2.0 + 75.0, 24.0, YELLOW);
+            if result.assisted && played_mode.records_scores() {
+                let note = "Aim assist steered your shots, so this score was not recorded";
+                let note_dims = measure_text(note, None, 20, 1.0);
+                draw_text(note, screen_width() / 2.0 - note_dims.width / 2.0, screen_height() / 2.0 + 215.0, 20.0, LIGHTGRAY);
+            }
+
             let text_dims3 = measure_text(text2, None, 20, 1.0);
-            draw_text(text2, screen_width() / 2.0 - text_dims3.width / 2.0, screen_height() / 2.0 + 80.0, 20.0, WHITE);
+            draw_text(text2, screen_width() / 2.0 - text_dims3.width / 2.0, screen_height() / 2.0 + 110.0, 20.0, WHITE);
+
+            let difficulty_text = format!("LEFT/RIGHT to change difficulty: {}", difficulty.name());
+            let difficulty_dims = measure_text(&difficulty_text, None, 20, 1.0);
+            draw_text(&difficulty_text, screen_width() / 2.0 - difficulty_dims.width / 2.0, screen_height() / 2.0 + 135.0, 20.0, WHITE);
+            if is_key_pressed(KeyCode::Left) { difficulty = difficulty.cycle(-1); }
+            if is_key_pressed(KeyCode::Right) { difficulty = difficulty.cycle(1); }
+
+            let mode_text = format!("UP/DOWN to change mode: {}", mode.name());
+            let mode_dims = measure_text(&mode_text, None, 20, 1.0);
+            draw_text(&mode_text, screen_width() / 2.0 - mode_dims.width / 2.0, screen_height() / 2.0 + 160.0, 20.0, WHITE);
+            if is_key_pressed(KeyCode::Up) { mode = mode.cycle(-1); }
+            if is_key_pressed(KeyCode::Down) { mode = mode.cycle(1); }
+
+            // Capture the results before the confirmation is drawn over them.
+            if is_key_pressed(KeyCode::S) {
+                save_message = match save_screenshot() {
+                    Ok(_) => "Saved!".to_string(),
+                    Err(e) => {
+                        eprintln!("Failed to save screenshot: {}", e);
+                        "Screenshot failed".to_string()
+                    }
+                };
+                save_message_timer = 1.5;
+            }
+            if save_message_timer > 0.0 {
+                save_message_timer -= get_frame_time();
+                let text_dims4 = measure_text(&save_message, None, 20, 1.0);
+                draw_text(&save_message, screen_width() / 2.0 - text_dims4.width / 2.0, screen_height() / 2.0 + 190.0, 20.0, YELLOW);
+            }
+
+            if is_key_pressed(KeyCode::N) { toggle_mute(&mut config); }
+            draw_mute_icon(&config);
 
             // Check for restart input.
             if is_key_pressed(KeyCode::Enter) {
                 break;
             }
 
+            limiter.wait(config.frame_cap);
             next_frame().await
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn enemy_bullets_never_exceed_cap() {
+        let cap = 5;
+        let mut enemy_bullets = vec![];
+        let fired = (0..20)
+            .filter(|_| spawn_enemy_bullet(&mut enemy_bullets, EnemyBullet { pos: Vec2::ZERO, vel: Vec2::Y, gravity: 0.0 }, cap))
+            .count();
+        assert_eq!(fired, cap);
+        assert_eq!(enemy_bullets.len(), cap);
+    }
+
+    #[test]
+    fn enemy_bullets_resume_once_below_cap() {
+        let cap = 2;
+        let mut enemy_bullets = vec![];
+        for _ in 0..cap {
+            spawn_enemy_bullet(&mut enemy_bullets, EnemyBullet { pos: Vec2::ZERO, vel: Vec2::Y, gravity: 0.0 }, cap);
+        }
+        enemy_bullets.pop();
+        assert!(spawn_enemy_bullet(&mut enemy_bullets, EnemyBullet { pos: Vec2::ZERO, vel: Vec2::Y, gravity: 0.0 }, cap));
+        assert!(!spawn_enemy_bullet(&mut enemy_bullets, EnemyBullet { pos: Vec2::ZERO, vel: Vec2::Y, gravity: 0.0 }, cap));
+        assert_eq!(enemy_bullets.len(), cap);
+    }
+}
@@ -0,0 +1,21 @@
+// What the player earns for crossing a milestone score.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum MilestoneReward {
+    Bomb,
+    WeaponUpgrade,
+}
+
+// A score threshold in endless play and the reward for reaching it.
+pub struct Milestone {
+    pub score: u32,
+    pub reward: MilestoneReward,
+}
+
+// Returns every milestone the score has newly reached, advancing `next` past them.
+pub fn reached_milestones<'a>(milestones: &'a [Milestone], next: &mut usize, score: u32) -> &'a [Milestone] {
+    let start = *next;
+    while milestones.get(*next).is_some_and(|m| score >= m.score) {
+        *next += 1;
+    }
+    &milestones[start..*next]
+}
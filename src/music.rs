@@ -0,0 +1,63 @@
+use macroquad::audio::{load_sound, play_sound, set_sound_volume, stop_sound, PlaySoundParams, Sound};
+
+use crate::{play_sfx, GameConfig};
+
+// The looping background tracks and the cue played as a boss arrives.
+// Each is optional, so missing files only leave that part of the music silent.
+pub struct MusicTracks {
+    normal: Option<Sound>,
+    boss: Option<Sound>,
+    stinger: Option<Sound>,
+}
+
+impl MusicTracks {
+    pub async fn load() -> MusicTracks {
+        MusicTracks {
+            normal: load_sound("assets/music.wav").await.ok(),
+            boss: load_sound("assets/boss_music.wav").await.ok(),
+            stinger: load_sound("assets/boss_stinger.wav").await.ok(),
+        }
+    }
+
+    // Starts both tracks looping together, with only the normal track audible.
+    pub fn start(&self, config: &GameConfig) {
+        if let Some(normal) = &self.normal {
+            play_sound(normal, PlaySoundParams { looped: true, volume: config.music_volume });
+        }
+        if let Some(boss) = &self.boss {
+            play_sound(boss, PlaySoundParams { looped: true, volume: 0.0 });
+        }
+    }
+
+    pub fn stop(&self) {
+        for track in [&self.normal, &self.boss].into_iter().flatten() {
+            stop_sound(track);
+        }
+    }
+
+    pub fn play_stinger(&self, config: &GameConfig) {
+        if let Some(stinger) = &self.stinger {
+            play_sfx(stinger, config.music_volume);
+        }
+    }
+
+    // Fades `boss_mix` toward the boss track while a boss is active and back afterward, then applies it.
+    // Without a boss track the normal track simply keeps playing.
+    pub fn crossfade(&self, boss_mix: &mut f32, boss_active: bool, dt: f32, config: &GameConfig) {
+        if self.boss.is_none() { return; }
+        let step = dt / config.music_fade_time;
+        *boss_mix = if boss_active { (*boss_mix + step).min(1.0) } else { (*boss_mix - step).max(0.0) };
+        self.apply_volume(*boss_mix, config);
+    }
+
+    // Sets both tracks' volumes for the given blend toward the boss track.
+    pub fn apply_volume(&self, boss_mix: f32, config: &GameConfig) {
+        let normal_share = if self.boss.is_some() { 1.0 - boss_mix } else { 1.0 };
+        if let Some(normal) = &self.normal {
+            set_sound_volume(normal, config.music_volume * normal_share);
+        }
+        if let Some(boss) = &self.boss {
+            set_sound_volume(boss, config.music_volume * boss_mix);
+        }
+    }
+}
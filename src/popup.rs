@@ -0,0 +1,40 @@
+use macroquad::prelude::*;
+
+// How long a popup stays on screen, in seconds.
+const POPUP_LIFETIME: f32 = 1.2;
+// How fast a popup drifts upward, in pixels per second.
+const POPUP_RISE_SPEED: f32 = 40.0;
+
+// A line of floating text, such as points awarded, that drifts up and fades out.
+pub struct ScorePopup {
+    pub pos: Vec2,
+    pub text: String,
+    pub color: Color,
+    pub size: f32,
+    timer: f32,
+}
+
+impl ScorePopup {
+    pub fn new(pos: Vec2, text: String, color: Color, size: f32) -> ScorePopup {
+        ScorePopup { pos, text, color, size, timer: POPUP_LIFETIME }
+    }
+}
+
+// Moves popups upward and removes those that have faded out.
+pub fn update_popups(popups: &mut Vec<ScorePopup>, dt: f32) {
+    for popup in popups.iter_mut() {
+        popup.timer -= dt;
+        popup.pos.y -= POPUP_RISE_SPEED * dt;
+    }
+    popups.retain(|p| p.timer > 0.0);
+}
+
+// Draws each popup centered on its position, fading as it ages.
+pub fn draw_popups(popups: &[ScorePopup]) {
+    for popup in popups {
+        let alpha = (popup.timer / POPUP_LIFETIME).min(1.0);
+        let color = Color::new(popup.color.r, popup.color.g, popup.color.b, alpha);
+        let dims = measure_text(&popup.text, None, popup.size as u16, 1.0);
+        draw_text(&popup.text, popup.pos.x - dims.width / 2.0, popup.pos.y, popup.size, color);
+    }
+}
@@ -0,0 +1,35 @@
+use macroquad::prelude::*;
+
+// Width and height of a pickup's collection box.
+pub const POWERUP_SIZE: f32 = 32.0;
+// The glow every pickup is drawn in, which decoys imitate.
+pub const POWERUP_COLOR: Color = Color::new(0.4, 0.8, 1.0, 1.0);
+
+// A bomb dropped by a destroyed enemy that drifts down until collected or lost off the bottom.
+pub struct PowerUp {
+    // Center of the pickup.
+    pub pos: Vec2,
+}
+
+impl PowerUp {
+    pub fn hitbox(&self) -> Rect {
+        Rect::new(self.pos.x - POWERUP_SIZE / 2.0, self.pos.y - POWERUP_SIZE / 2.0, POWERUP_SIZE, POWERUP_SIZE)
+    }
+}
+
+// Drops pickups at their fall speed and forgets those that have fallen out of the playfield.
+pub fn update_powerups(powerups: &mut Vec<PowerUp>, fall_speed: f32, field: Rect, dt: f32) {
+    for powerup in powerups.iter_mut() {
+        powerup.pos.y += fall_speed * dt;
+    }
+    powerups.retain(|p| p.pos.y < field.bottom() + POWERUP_SIZE);
+}
+
+// Draws the pickup look centered on `center`, shared by real pickups and the decoys that copy them.
+pub fn draw_powerup_shape(center: Vec2, color: Color) {
+    let pulse = (get_time() as f32 * 6.0).sin() * 0.1 + 0.9;
+    draw_circle(center.x, center.y, POWERUP_SIZE / 2.0 * pulse, Color::new(color.r, color.g, color.b, 0.35));
+    draw_circle_lines(center.x, center.y, POWERUP_SIZE / 2.0 * pulse, 2.0, color);
+    let dims = measure_text("B", None, 22, 1.0);
+    draw_text("B", center.x - dims.width / 2.0, center.y + dims.height / 2.0, 22.0, color);
+}
@@ -0,0 +1,31 @@
+// Damage dealt to the practice dummy, kept for weapon tuning.
+pub struct DummyStats {
+    total: u32,
+    // Time and amount of each recent hit, oldest first, for the DPS window.
+    recent: Vec<(f64, u32)>,
+}
+
+impl DummyStats {
+    pub fn new() -> DummyStats {
+        DummyStats { total: 0, recent: vec![] }
+    }
+
+    pub fn record(&mut self, now: f64, damage: u32) {
+        self.total += damage;
+        self.recent.push((now, damage));
+    }
+
+    pub fn total(&self) -> u32 {
+        self.total
+    }
+
+    // Returns the damage per second over the last `window` seconds, dropping older hits.
+    pub fn dps(&mut self, now: f64, window: f32) -> f32 {
+        self.recent.retain(|&(time, _)| now - time <= window as f64);
+        self.recent.iter().map(|&(_, damage)| damage).sum::<u32>() as f32 / window
+    }
+
+    pub fn reset(&mut self) {
+        *self = DummyStats::new();
+    }
+}
@@ -0,0 +1,53 @@
+use macroquad::prelude::*;
+use std::collections::VecDeque;
+
+use crate::enemy::EnemyKind;
+use crate::gravity_well::GravityWell;
+use crate::shockwave::Shockwave;
+
+// Where everything stood on one frame, kept so the final moments before a death can be replayed.
+pub struct ReplayFrame {
+    pub time: f32,
+    pub player: Vec2,
+    // Each enemy's position, kind and tint; the kind lets decoys be shown in their pickup disguise.
+    pub enemies: Vec<(Vec2, EnemyKind, Color)>,
+    pub bullets: Vec<Vec2>,
+    pub enemy_bullets: Vec<(Vec2, Color)>,
+    pub boss: Option<Rect>,
+    // The ends of the boss's laser beam and whether it was still only a warning line.
+    pub boss_laser: Option<(Vec2, Vec2, bool)>,
+    pub shockwaves: Vec<Shockwave>,
+    pub gravity_wells: Vec<GravityWell>,
+}
+
+// A rolling record of the last few seconds of play.
+pub struct ReplayBuffer {
+    frames: VecDeque<ReplayFrame>,
+    // Seconds of play to keep, measured back from the newest frame.
+    window: f32,
+}
+
+impl ReplayBuffer {
+    pub fn new(window: f32) -> ReplayBuffer {
+        ReplayBuffer { frames: VecDeque::new(), window }
+    }
+
+    // Adds a frame and forgets those that have fallen out of the window.
+    pub fn push(&mut self, frame: ReplayFrame) {
+        let cutoff = frame.time - self.window;
+        self.frames.push_back(frame);
+        while self.frames.front().is_some_and(|f| f.time < cutoff) {
+            self.frames.pop_front();
+        }
+    }
+
+    // Returns the time span covered by the buffer, if anything has been recorded.
+    pub fn span(&self) -> Option<(f32, f32)> {
+        Some((self.frames.front()?.time, self.frames.back()?.time))
+    }
+
+    // Returns the latest frame recorded at or before the given time.
+    pub fn frame_at(&self, time: f32) -> Option<&ReplayFrame> {
+        self.frames.iter().rev().find(|f| f.time <= time).or(self.frames.front())
+    }
+}
@@ -0,0 +1,96 @@
+// How scores are written out on the HUD and results screen.
+#[derive(Clone, Copy, PartialEq)]
+pub enum ScoreStyle {
+    // Digits only, e.g. "12345".
+    Plain,
+    // Digits grouped with thousands separators, e.g. "12,345".
+    Separated,
+    // Separated, but scores of a million or more are shortened, e.g. "1.2M".
+    Abbreviated,
+}
+
+impl ScoreStyle {
+    pub const ALL: [ScoreStyle; 3] = [ScoreStyle::Plain, ScoreStyle::Separated, ScoreStyle::Abbreviated];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ScoreStyle::Plain => "Plain",
+            ScoreStyle::Separated => "Separated",
+            ScoreStyle::Abbreviated => "Abbreviated",
+        }
+    }
+}
+
+// Formats a score for display in the given style.
+pub fn format_score(n: u32, style: ScoreStyle) -> String {
+    match style {
+        ScoreStyle::Plain => n.to_string(),
+        ScoreStyle::Separated => with_separators(n),
+        ScoreStyle::Abbreviated => {
+            let (unit, suffix) = match n {
+                1_000_000_000.. => (1_000_000_000, "B"),
+                1_000_000.. => (1_000_000, "M"),
+                _ => return with_separators(n),
+            };
+            // Truncate to one decimal place so the value never rounds up past what was scored.
+            let tenths = n / (unit / 10);
+            if tenths.is_multiple_of(10) {
+                format!("{}{}", tenths / 10, suffix)
+            } else {
+                format!("{}.{}{}", tenths / 10, tenths % 10, suffix)
+            }
+        }
+    }
+}
+
+// Inserts a comma between every group of three digits.
+fn with_separators(n: u32) -> String {
+    let digits = n.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_leaves_digits_alone() {
+        assert_eq!(format_score(0, ScoreStyle::Plain), "0");
+        assert_eq!(format_score(1234567, ScoreStyle::Plain), "1234567");
+    }
+
+    #[test]
+    fn separated_groups_thousands() {
+        assert_eq!(format_score(0, ScoreStyle::Separated), "0");
+        assert_eq!(format_score(999, ScoreStyle::Separated), "999");
+        assert_eq!(format_score(1000, ScoreStyle::Separated), "1,000");
+        assert_eq!(format_score(12345, ScoreStyle::Separated), "12,345");
+        assert_eq!(format_score(123456, ScoreStyle::Separated), "123,456");
+        assert_eq!(format_score(1234567, ScoreStyle::Separated), "1,234,567");
+        assert_eq!(format_score(u32::MAX, ScoreStyle::Separated), "4,294,967,295");
+    }
+
+    #[test]
+    fn abbreviated_keeps_small_scores_separated() {
+        assert_eq!(format_score(42, ScoreStyle::Abbreviated), "42");
+        assert_eq!(format_score(999_999, ScoreStyle::Abbreviated), "999,999");
+    }
+
+    #[test]
+    fn abbreviated_shortens_large_scores() {
+        assert_eq!(format_score(1_000_000, ScoreStyle::Abbreviated), "1M");
+        assert_eq!(format_score(1_250_000, ScoreStyle::Abbreviated), "1.2M");
+        assert_eq!(format_score(1_299_999, ScoreStyle::Abbreviated), "1.2M");
+        assert_eq!(format_score(10_500_000, ScoreStyle::Abbreviated), "10.5M");
+        assert_eq!(format_score(999_999_999, ScoreStyle::Abbreviated), "999.9M");
+        assert_eq!(format_score(3_000_000_000, ScoreStyle::Abbreviated), "3B");
+        assert_eq!(format_score(u32::MAX, ScoreStyle::Abbreviated), "4.2B");
+    }
+}
@@ -0,0 +1,289 @@
+use macroquad::prelude::*;
+
+use crate::score::ScoreStyle;
+use crate::weapon::AimAssist;
+use crate::{DifficultyCurve, EscapeRule, GameConfig, GameMode};
+
+// Frame rate limits the frame cap setting cycles through, with 0 meaning uncapped.
+const FRAME_CAPS: [u32; 4] = [0, 30, 60, 120];
+// Camera zoom levels the zoom setting cycles through.
+const ZOOM_LEVELS: [f32; 4] = [0.75, 1.0, 1.25, 1.5];
+// Player movement ranges the movement setting cycles through, with their names in the settings file and menu.
+const MOVEMENT_RANGES: [(&str, &str, Rect); 3] = [
+    ("bottom", "Bottom edge", GameConfig::BOTTOM_LOCKED),
+    ("lower_third", "Lower third", GameConfig::BOTTOM_THIRD),
+    ("full", "Full screen", GameConfig::FULL_SCREEN),
+];
+
+// The options a player can change while a round is running, saved between sessions.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Setting {
+    SfxVolume,
+    MusicVolume,
+    ReduceEffects,
+    DamageNumbers,
+    FiringLine,
+    AimAssist,
+    DangerMeter,
+    DifficultyCurve,
+    // What letting an enemy through costs in the given mode.
+    Escapes(GameMode),
+    MovementRange,
+    MovementSmoothing,
+    Zoom,
+    FrameCap,
+    ScoreStyle,
+    // Whether a full second wind charge can revive the player in the given mode.
+    SecondWind(GameMode),
+}
+
+impl Setting {
+    pub const ALL: [Setting; 17] = [
+        Setting::SfxVolume,
+        Setting::MusicVolume,
+        Setting::ReduceEffects,
+        Setting::DamageNumbers,
+        Setting::FiringLine,
+        Setting::AimAssist,
+        Setting::DangerMeter,
+        Setting::DifficultyCurve,
+        Setting::Escapes(GameMode::Classic),
+        Setting::Escapes(GameMode::Waves),
+        Setting::MovementRange,
+        Setting::MovementSmoothing,
+        Setting::Zoom,
+        Setting::FrameCap,
+        Setting::ScoreStyle,
+        Setting::SecondWind(GameMode::Classic),
+        Setting::SecondWind(GameMode::Waves),
+    ];
+
+    // Returns the menu line for this setting with its current value.
+    fn label(self, config: &GameConfig) -> String {
+        match self {
+            Setting::SfxVolume => format!("Sound effects: {:.0}%", config.sfx_volume * 100.0),
+            Setting::MusicVolume => format!("Music: {:.0}%", config.music_volume * 100.0),
+            Setting::ReduceEffects => format!("Reduce effects: {}", on_off(config.reduce_effects)),
+            Setting::DamageNumbers => format!("Damage numbers: {}", on_off(config.show_damage_numbers)),
+            Setting::FiringLine => format!("Firing line: {}", on_off(config.show_firing_line)),
+            Setting::AimAssist => format!("Aim assist: {}", config.aim_assist.name()),
+            Setting::DangerMeter => format!("Danger meter: {}", on_off(config.show_danger_meter)),
+            Setting::DifficultyCurve => format!("Difficulty curve: {}", config.difficulty_curve.name()),
+            Setting::Escapes(mode) => format!("Escapes in {}: {}", mode.name(), escape_rule(config, mode).name()),
+            Setting::MovementRange => match MOVEMENT_RANGES.iter().find(|(_, _, range)| *range == config.player_bounds) {
+                Some((_, name, _)) => format!("Movement: {}", name),
+                None => "Movement: Custom".to_string(),
+            },
+            Setting::MovementSmoothing => match config.movement_smoothing {
+                0.0 => "Movement smoothing: Off".to_string(),
+                smoothing => format!("Movement smoothing: {:.0}%", smoothing * 100.0),
+            },
+            Setting::Zoom => format!("Zoom: {:.0}%", config.camera_zoom * 100.0),
+            Setting::FrameCap => match config.frame_cap {
+                0 => "Frame cap: Off".to_string(),
+                cap => format!("Frame cap: {} FPS", cap),
+            },
+            Setting::ScoreStyle => format!("Score style: {}", config.score_style.name()),
+            Setting::SecondWind(mode) => format!("Second wind in {}: {}", mode.name(), on_off(config.second_wind_modes.contains(&mode))),
+        }
+    }
+
+    // Changes the setting one notch up (or down if `step` is negative).
+    pub fn adjust(self, config: &mut GameConfig, step: i32) {
+        match self {
+            // Turning a volume up or down while muted starts from the levels the mute put aside.
+            Setting::SfxVolume | Setting::MusicVolume if config.muted_levels.is_some() => {
+                toggle_mute(config);
+                self.adjust(config, step);
+            }
+            Setting::SfxVolume => config.sfx_volume = (config.sfx_volume + step as f32 * 0.1).clamp(0.0, 1.0),
+            Setting::MusicVolume => config.music_volume = (config.music_volume + step as f32 * 0.1).clamp(0.0, 1.0),
+            Setting::ReduceEffects => config.reduce_effects = !config.reduce_effects,
+            Setting::DamageNumbers => config.show_damage_numbers = !config.show_damage_numbers,
+            Setting::FiringLine => config.show_firing_line = !config.show_firing_line,
+            Setting::AimAssist => {
+                let index = AimAssist::ALL.iter().position(|&a| a == config.aim_assist).unwrap_or(0) as i32;
+                config.aim_assist = AimAssist::ALL[(index + step).rem_euclid(AimAssist::ALL.len() as i32) as usize];
+            }
+            Setting::DangerMeter => config.show_danger_meter = !config.show_danger_meter,
+            Setting::DifficultyCurve => {
+                let index = DifficultyCurve::ALL.iter().position(|&c| c == config.difficulty_curve).unwrap_or(0) as i32;
+                config.difficulty_curve = DifficultyCurve::ALL[(index + step).rem_euclid(DifficultyCurve::ALL.len() as i32) as usize];
+            }
+            Setting::Escapes(mode) => {
+                let index = EscapeRule::ALL.iter().position(|&r| r == escape_rule(config, mode)).unwrap_or(0) as i32;
+                config.escape_rules.insert(mode, EscapeRule::ALL[(index + step).rem_euclid(EscapeRule::ALL.len() as i32) as usize]);
+            }
+            Setting::MovementRange => {
+                let index = MOVEMENT_RANGES.iter().position(|(_, _, range)| *range == config.player_bounds).unwrap_or(0) as i32;
+                config.player_bounds = MOVEMENT_RANGES[(index + step).rem_euclid(MOVEMENT_RANGES.len() as i32) as usize].2;
+            }
+            Setting::MovementSmoothing => {
+                let smoothing = (config.movement_smoothing + step as f32 * 0.1).clamp(0.0, 1.0);
+                // Round away the drift from repeated steps so the off position is exactly zero.
+                config.movement_smoothing = (smoothing * 10.0).round() / 10.0;
+            }
+            Setting::Zoom => {
+                let index = ZOOM_LEVELS.iter().position(|&zoom| zoom == config.camera_zoom).unwrap_or(1) as i32;
+                config.camera_zoom = ZOOM_LEVELS[(index + step).clamp(0, ZOOM_LEVELS.len() as i32 - 1) as usize];
+            }
+            Setting::FrameCap => {
+                let index = FRAME_CAPS.iter().position(|&cap| cap == config.frame_cap).unwrap_or(0) as i32;
+                config.frame_cap = FRAME_CAPS[(index + step).rem_euclid(FRAME_CAPS.len() as i32) as usize];
+            }
+            Setting::ScoreStyle => {
+                let index = ScoreStyle::ALL.iter().position(|&s| s == config.score_style).unwrap_or(0) as i32;
+                config.score_style = ScoreStyle::ALL[(index + step).rem_euclid(ScoreStyle::ALL.len() as i32) as usize];
+            }
+            Setting::SecondWind(mode) => set_second_wind(config, mode, !config.second_wind_modes.contains(&mode)),
+        }
+    }
+}
+
+impl Setting {
+    // Name of the setting in the settings file.
+    fn key(self) -> &'static str {
+        match self {
+            Setting::SfxVolume => "sfx_volume",
+            Setting::MusicVolume => "music_volume",
+            Setting::ReduceEffects => "reduce_effects",
+            Setting::DamageNumbers => "damage_numbers",
+            Setting::FiringLine => "firing_line",
+            Setting::AimAssist => "aim_assist",
+            Setting::DangerMeter => "danger_meter",
+            Setting::DifficultyCurve => "difficulty_curve",
+            Setting::Escapes(GameMode::Classic) => "escapes_classic",
+            Setting::Escapes(GameMode::Waves) => "escapes_waves",
+            Setting::Escapes(GameMode::Practice) => "escapes_practice",
+            Setting::MovementRange => "movement_range",
+            Setting::MovementSmoothing => "movement_smoothing",
+            Setting::Zoom => "zoom",
+            Setting::FrameCap => "frame_cap",
+            Setting::ScoreStyle => "score_style",
+            Setting::SecondWind(GameMode::Classic) => "second_wind_classic",
+            Setting::SecondWind(GameMode::Waves) => "second_wind_waves",
+            Setting::SecondWind(GameMode::Practice) => "second_wind_practice",
+        }
+    }
+
+    // Returns the setting's current value as it is written to the settings file.
+    // Muting only lasts for the session, so the levels put aside by a mute are saved instead of silence.
+    fn saved_value(self, config: &GameConfig) -> String {
+        let (sfx_volume, music_volume) = config.muted_levels.unwrap_or((config.sfx_volume, config.music_volume));
+        match self {
+            Setting::SfxVolume => sfx_volume.to_string(),
+            Setting::MusicVolume => music_volume.to_string(),
+            Setting::ReduceEffects => config.reduce_effects.to_string(),
+            Setting::DamageNumbers => config.show_damage_numbers.to_string(),
+            Setting::FiringLine => config.show_firing_line.to_string(),
+            Setting::AimAssist => config.aim_assist.name().to_lowercase(),
+            Setting::DangerMeter => config.show_danger_meter.to_string(),
+            Setting::DifficultyCurve => config.difficulty_curve.name().to_lowercase(),
+            Setting::Escapes(mode) => escape_rule(config, mode).name().to_lowercase(),
+            // A range set in code that matches none of the presets is saved as "custom", which loading ignores.
+            Setting::MovementRange => MOVEMENT_RANGES.iter().find(|(_, _, range)| *range == config.player_bounds).map_or("custom", |(key, _, _)| key).to_string(),
+            Setting::MovementSmoothing => config.movement_smoothing.to_string(),
+            Setting::Zoom => config.camera_zoom.to_string(),
+            Setting::FrameCap => config.frame_cap.to_string(),
+            Setting::ScoreStyle => config.score_style.name().to_lowercase(),
+            Setting::SecondWind(mode) => config.second_wind_modes.contains(&mode).to_string(),
+        }
+    }
+
+    // Applies a value read from the settings file, keeping the current one if it does not parse.
+    fn load_value(self, config: &mut GameConfig, text: &str) {
+        match self {
+            Setting::SfxVolume => if let Ok(v) = text.parse::<f32>() { config.sfx_volume = v.clamp(0.0, 1.0) },
+            Setting::MusicVolume => if let Ok(v) = text.parse::<f32>() { config.music_volume = v.clamp(0.0, 1.0) },
+            Setting::ReduceEffects => if let Ok(v) = text.parse() { config.reduce_effects = v },
+            Setting::DamageNumbers => if let Ok(v) = text.parse() { config.show_damage_numbers = v },
+            Setting::FiringLine => if let Ok(v) = text.parse() { config.show_firing_line = v },
+            Setting::AimAssist => if let Some(v) = AimAssist::ALL.into_iter().find(|a| a.name().eq_ignore_ascii_case(text)) { config.aim_assist = v },
+            Setting::DangerMeter => if let Ok(v) = text.parse() { config.show_danger_meter = v },
+            Setting::DifficultyCurve => if let Some(v) = DifficultyCurve::ALL.into_iter().find(|c| c.name().eq_ignore_ascii_case(text)) { config.difficulty_curve = v },
+            Setting::Escapes(mode) => if let Some(v) = EscapeRule::ALL.into_iter().find(|r| r.name().eq_ignore_ascii_case(text)) { config.escape_rules.insert(mode, v); },
+            Setting::MovementRange => if let Some((_, _, range)) = MOVEMENT_RANGES.iter().find(|(key, _, _)| *key == text) { config.player_bounds = *range },
+            Setting::MovementSmoothing => if let Ok(v) = text.parse::<f32>() { config.movement_smoothing = v.clamp(0.0, 1.0) },
+            Setting::Zoom => if let Ok(v) = text.parse::<f32>() { config.camera_zoom = v.clamp(ZOOM_LEVELS[0], ZOOM_LEVELS[ZOOM_LEVELS.len() - 1]) },
+            Setting::FrameCap => if let Ok(v) = text.parse() { config.frame_cap = v },
+            Setting::ScoreStyle => if let Some(v) = ScoreStyle::ALL.into_iter().find(|s| s.name().eq_ignore_ascii_case(text)) { config.score_style = v },
+            Setting::SecondWind(mode) => if let Ok(v) = text.parse() { set_second_wind(config, mode, v) },
+        }
+    }
+}
+
+// Reads saved settings, one "name value" line each, into the config.
+// A missing file or unknown lines leave the defaults in place.
+pub fn load_settings(config: &mut GameConfig, path: &str) {
+    let Ok(contents) = std::fs::read_to_string(path) else { return };
+    for line in contents.lines() {
+        let mut parts = line.split_whitespace();
+        let (Some(key), Some(value)) = (parts.next(), parts.next()) else { continue };
+        if let Some(setting) = Setting::ALL.into_iter().find(|s| s.key() == key) {
+            setting.load_value(config, value);
+        }
+    }
+}
+
+pub fn save_settings(config: &GameConfig, path: &str) -> std::io::Result<()> {
+    let contents: String = Setting::ALL.iter().map(|s| format!("{} {}\n", s.key(), s.saved_value(config))).collect();
+    std::fs::write(path, contents)
+}
+
+// Silences all sound by zeroing both volumes, or brings back the levels from before the mute.
+// The caller still has to apply the new music volume to any playing tracks.
+pub fn toggle_mute(config: &mut GameConfig) {
+    match config.muted_levels.take() {
+        Some((sfx_volume, music_volume)) => {
+            config.sfx_volume = sfx_volume;
+            config.music_volume = music_volume;
+        }
+        None => {
+            config.muted_levels = Some((config.sfx_volume, config.music_volume));
+            config.sfx_volume = 0.0;
+            config.music_volume = 0.0;
+        }
+    }
+}
+
+// Draws a crossed-out speaker in the bottom-right corner while everything is muted.
+pub fn draw_mute_icon(config: &GameConfig) {
+    if config.muted_levels.is_none() { return; }
+    let (x, y) = (screen_width() - 60.0, screen_height() - 40.0);
+    let color = Color::new(1.0, 1.0, 1.0, 0.7);
+    draw_rectangle(x, y - 6.0, 8.0, 12.0, color);
+    draw_triangle(vec2(x + 4.0, y), vec2(x + 18.0, y - 13.0), vec2(x + 18.0, y + 13.0), color);
+    draw_line(x + 24.0, y - 7.0, x + 38.0, y + 7.0, 3.0, RED);
+    draw_line(x + 24.0, y + 7.0, x + 38.0, y - 7.0, 3.0, RED);
+}
+
+fn on_off(enabled: bool) -> &'static str {
+    if enabled { "On" } else { "Off" }
+}
+
+// Returns the escape rule a mode is played under; modes left out of the config let enemies go for free.
+fn escape_rule(config: &GameConfig, mode: GameMode) -> EscapeRule {
+    config.escape_rules.get(&mode).copied().unwrap_or(EscapeRule::Free)
+}
+
+// Turns second wind on or off for a mode, listing each mode at most once.
+fn set_second_wind(config: &mut GameConfig, mode: GameMode, enabled: bool) {
+    config.second_wind_modes.retain(|&m| m != mode);
+    if enabled {
+        config.second_wind_modes.push(mode);
+    }
+}
+
+// Draws the settings list centered across the screen from `top` down, highlighting the selected entry,
+// and returns the height just below the last line.
+pub fn draw_settings(config: &GameConfig, selected: usize, top: f32) -> f32 {
+    let mut y = top;
+    for (i, setting) in Setting::ALL.iter().enumerate() {
+        let text = setting.label(config);
+        let text_dims = measure_text(&text, None, 22, 1.0);
+        let color = if i == selected { YELLOW } else { WHITE };
+        draw_text(&text, screen_width() / 2.0 - text_dims.width / 2.0, y, 22.0, color);
+        y += 24.0;
+    }
+    y
+}
@@ -0,0 +1,51 @@
+use macroquad::prelude::*;
+use std::f32::consts::{FRAC_PI_4, TAU};
+
+use crate::GameConfig;
+
+// Width of the damaging band of a shockwave ring, in pixels.
+const RING_THICKNESS: f32 = 10.0;
+
+// An expanding ring released by a shocker. Only the ring itself is dangerous, and it has
+// a gap the player can slip through.
+#[derive(Clone)]
+pub struct Shockwave {
+    pub center: Vec2,
+    pub radius: f32,
+    // Direction of the middle of the gap, in radians, measured the way screen angles turn.
+    gap_angle: f32,
+}
+
+impl Shockwave {
+    // Starts a ring at the given point with its gap somewhere in the lower half, toward the player's side.
+    pub fn new(center: Vec2) -> Shockwave {
+        Shockwave { center, radius: 0.0, gap_angle: rand::gen_range(FRAC_PI_4, 3.0 * FRAC_PI_4) }
+    }
+
+    // Returns whether the ring's band touches a circle of radius `reach` around `point`, outside the gap.
+    pub fn hits(&self, point: Vec2, reach: f32, config: &GameConfig) -> bool {
+        let offset = point - self.center;
+        let in_band = (offset.length() - self.radius).abs() <= RING_THICKNESS / 2.0 + reach;
+        let from_gap = (offset.y.atan2(offset.x) - self.gap_angle + TAU / 2.0).rem_euclid(TAU) - TAU / 2.0;
+        in_band && from_gap.abs() > config.shockwave_gap / 2.0
+    }
+}
+
+// Grows every ring and drops those that have expanded past the whole screen.
+pub fn update_shockwaves(shockwaves: &mut Vec<Shockwave>, config: &GameConfig, dt: f32) {
+    let max_radius = vec2(screen_width(), screen_height()).length();
+    for shockwave in shockwaves.iter_mut() {
+        shockwave.radius += config.shockwave_speed * dt;
+    }
+    shockwaves.retain(|s| s.radius < max_radius);
+}
+
+// Draws each ring as an expanding outline broken by its gap.
+pub fn draw_shockwaves(shockwaves: &[Shockwave], config: &GameConfig) {
+    let gap_degrees = config.shockwave_gap.to_degrees();
+    for shockwave in shockwaves {
+        let start = shockwave.gap_angle.to_degrees() + gap_degrees / 2.0;
+        let inner_radius = (shockwave.radius - RING_THICKNESS / 2.0).max(0.0);
+        draw_arc(shockwave.center.x, shockwave.center.y, 96, inner_radius, start, RING_THICKNESS, 360.0 - gap_degrees, Color::new(0.6, 0.9, 1.0, 0.8));
+    }
+}
@@ -0,0 +1,74 @@
+use crate::GameConfig;
+
+// Progress through the numbered waves of wave mode.
+pub struct WaveState {
+    pub number: u32,
+    // Enemies this wave still has to release.
+    to_spawn: u32,
+    // Total enemies in this wave, used to scale the par time.
+    size: u32,
+    // Seconds since this wave released its last enemy, so the spawn schedule is not held against the player.
+    elapsed: f32,
+    // Enemies of this wave that got away alive, which earn no share of the speed bonus.
+    escaped: u32,
+    // Seconds left in the pause before the next wave, while between waves.
+    pub break_timer: f32,
+}
+
+// The outcome of clearing a wave.
+pub struct WaveClear {
+    pub number: u32,
+    pub bonus: u32,
+}
+
+impl WaveState {
+    pub fn new(config: &GameConfig) -> WaveState {
+        WaveState { number: 0, to_spawn: 0, size: 0, elapsed: 0.0, escaped: 0, break_timer: config.wave_break_time }
+    }
+
+    // Returns true while the current wave still has enemies to release.
+    pub fn can_spawn(&self) -> bool {
+        self.break_timer <= 0.0 && self.to_spawn > 0
+    }
+
+    pub fn on_spawn(&mut self) {
+        self.to_spawn = self.to_spawn.saturating_sub(1);
+        if self.to_spawn == 0 {
+            self.elapsed = 0.0;
+        }
+    }
+
+    pub fn on_escape(&mut self, count: u32) {
+        self.escaped += count;
+    }
+
+    // Advances the wave clock, starting the next wave after a break and reporting when a wave is cleared.
+    pub fn update(&mut self, dt: f32, field_clear: bool, config: &GameConfig) -> Option<WaveClear> {
+        if self.break_timer > 0.0 {
+            self.break_timer -= dt;
+            if self.break_timer <= 0.0 {
+                self.number += 1;
+                self.size = config.wave_base_size + (self.number - 1) * config.wave_size_step;
+                self.to_spawn = self.size;
+                self.escaped = 0;
+            }
+            return None;
+        }
+        self.elapsed += dt;
+        if self.to_spawn > 0 || !field_clear {
+            return None;
+        }
+        self.break_timer = config.wave_break_time;
+        let caught = self.size.saturating_sub(self.escaped) as f32 / self.size.max(1) as f32;
+        let bonus = (wave_time_bonus(self.elapsed, self.size, config) as f32 * caught).round() as u32;
+        Some(WaveClear { number: self.number, bonus })
+    }
+}
+
+// Returns the speed bonus for clearing a wave of `size` enemies `clear_time` seconds after its last one was released.
+// The bonus falls from its maximum at an instant clear to nothing at the par time, shaped by the curve exponent.
+pub fn wave_time_bonus(clear_time: f32, size: u32, config: &GameConfig) -> u32 {
+    let par_time = config.wave_par_time_per_enemy * size as f32;
+    let remaining = (1.0 - clear_time / par_time).clamp(0.0, 1.0);
+    (config.wave_bonus_max as f32 * remaining.powf(config.wave_bonus_exponent)).round() as u32
+}
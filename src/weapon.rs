@@ -0,0 +1,124 @@
+use macroquad::prelude::*;
+
+use crate::Bullet;
+
+// The guns the player can fire.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Weapon {
+    // A single shot straight up.
+    Blaster,
+    // Three shots fanned out, fired a little slower.
+    Spread,
+    // A single shot with a much shorter cooldown.
+    Rapid,
+}
+
+impl Weapon {
+    // Every weapon, in the order they are unlocked.
+    const ALL: [Weapon; 3] = [Weapon::Blaster, Weapon::Spread, Weapon::Rapid];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Weapon::Blaster => "Blaster",
+            Weapon::Spread => "Spread",
+            Weapon::Rapid => "Rapid",
+        }
+    }
+
+    // Seconds between shots.
+    pub fn cooldown(self) -> f32 {
+        match self {
+            Weapon::Blaster => 0.4,
+            Weapon::Spread => 0.55,
+            Weapon::Rapid => 0.15,
+        }
+    }
+
+    // Returns the bullets fired from the given muzzle position.
+    pub fn fire(self, origin: Vec2, speed: f32) -> Vec<Bullet> {
+        let angles: &[f32] = match self {
+            Weapon::Blaster | Weapon::Rapid => &[0.0],
+            Weapon::Spread => &[-0.25, 0.0, 0.25],
+        };
+        angles
+            .iter()
+            .map(|angle| Bullet { pos: origin, vel: vec2(angle.sin(), -angle.cos()) * speed })
+            .collect()
+    }
+}
+
+// How strongly straight shots are bent toward a nearby enemy, as an accessibility aid.
+#[derive(Clone, Copy, PartialEq)]
+pub enum AimAssist {
+    Off,
+    Low,
+    High,
+}
+
+impl AimAssist {
+    pub const ALL: [AimAssist; 3] = [AimAssist::Off, AimAssist::Low, AimAssist::High];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AimAssist::Off => "Off",
+            AimAssist::Low => "Low",
+            AimAssist::High => "High",
+        }
+    }
+
+    // Turns a straight-up shot slightly toward the nearest target inside the assist cone, returning whether it turned.
+    pub fn nudge(self, bullet: &mut Bullet, targets: &[Vec2]) -> bool {
+        // Half-width of the cone searched for targets and the most the shot may turn, both in radians.
+        let (cone, max_turn) = match self {
+            AimAssist::Off => return false,
+            AimAssist::Low => (0.15, 0.05),
+            AimAssist::High => (0.3, 0.12),
+        };
+        if bullet.vel.x != 0.0 {
+            return false;
+        }
+        let dir = bullet.vel.normalize();
+        let nearest = targets
+            .iter()
+            .map(|&target| target - bullet.pos)
+            .filter(|to| to.y < 0.0 && dir.angle_between(*to).abs() <= cone)
+            .min_by(|a, b| a.length().total_cmp(&b.length()));
+        let Some(to) = nearest else { return false };
+        let turn = dir.angle_between(to).clamp(-max_turn, max_turn);
+        bullet.vel = Vec2::from_angle(turn).rotate(bullet.vel);
+        turn != 0.0
+    }
+}
+
+// The weapons the player owns in the current round and which one is equipped.
+pub struct Loadout {
+    owned: Vec<Weapon>,
+    current: usize,
+}
+
+impl Loadout {
+    pub fn new() -> Loadout {
+        Loadout { owned: vec![Weapon::Blaster], current: 0 }
+    }
+
+    pub fn current(&self) -> Weapon {
+        self.owned[self.current]
+    }
+
+    // Equips the next owned weapon, wrapping around, and returns it.
+    pub fn cycle(&mut self) -> Weapon {
+        self.current = (self.current + 1) % self.owned.len();
+        self.current()
+    }
+
+    pub fn owns_several(&self) -> bool {
+        self.owned.len() > 1
+    }
+
+    // Adds the next weapon not yet owned, returning it, or none if all are unlocked.
+    pub fn unlock_next(&mut self) -> Option<Weapon> {
+        let next = Weapon::ALL.into_iter().find(|w| !self.owned.contains(w))?;
+        self.owned.push(next);
+        Some(next)
+    }
+}